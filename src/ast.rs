@@ -0,0 +1,41 @@
+/// A term of the untyped lambda calculus, as read by `parser::parse`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LambdaExpression {
+    Variable(String),
+    Abstraction(String, Box<LambdaExpression>),
+    Application(Box<LambdaExpression>, Box<LambdaExpression>),
+}
+
+impl LambdaExpression {
+    /// The number of variables, abstractions and applications in the term.
+    pub fn size(&self) -> usize {
+        match self {
+            LambdaExpression::Variable(_) => 1,
+            LambdaExpression::Abstraction(_, body) => 1 + body.size(),
+            LambdaExpression::Application(function, argument) => {
+                1 + function.size() + argument.size()
+            }
+        }
+    }
+
+    /// How deeply the term is nested, counting the variable and every
+    /// abstraction and application on the longest way down to it. Functions
+    /// working on terms recursively go this deep, so it is measured without
+    /// recursing.
+    pub fn depth(&self) -> usize {
+        let mut deepest = 0;
+        let mut open = vec![(self, 1)];
+        while let Some((expr, depth)) = open.pop() {
+            deepest = deepest.max(depth);
+            match expr {
+                LambdaExpression::Variable(_) => {}
+                LambdaExpression::Abstraction(_, body) => open.push((body, depth + 1)),
+                LambdaExpression::Application(function, argument) => {
+                    open.push((function, depth + 1));
+                    open.push((argument, depth + 1));
+                }
+            }
+        }
+        deepest
+    }
+}
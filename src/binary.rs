@@ -0,0 +1,254 @@
+use std::fmt;
+
+use crate::de_bruijn::{from_de_bruijn, to_de_bruijn, DeBruijn};
+use crate::ast::LambdaExpression;
+
+/// Why a term couldn't be encoded or a bit string decoded, positions count
+/// bits from the start.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BinaryError {
+    /// only closed terms can be encoded, free variables have no index
+    FreeVariable(String),
+    /// the bits end in the middle of a term
+    UnexpectedEnd,
+    /// a variable refers past the outermost abstraction
+    UnboundIndex {
+        index: usize,
+        position: usize,
+    },
+    /// bits are left after a complete term
+    TrailingBits {
+        position: usize,
+    },
+    InvalidCharacter {
+        character: char,
+        position: usize,
+    },
+}
+
+impl fmt::Display for BinaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryError::FreeVariable(name) => {
+                write!(f, "free variable `{}` can't be encoded", name)
+            }
+            BinaryError::UnexpectedEnd => write!(f, "unexpected end of the bits"),
+            BinaryError::UnboundIndex { index, position } => write!(
+                f,
+                "variable {} at bit {} is not bound by an abstraction",
+                index, position
+            ),
+            BinaryError::TrailingBits { position } => {
+                write!(f, "bits left after the term at bit {}", position)
+            }
+            BinaryError::InvalidCharacter {
+                character,
+                position,
+            } => write!(f, "`{}` at {} is not a bit", character, position),
+        }
+    }
+}
+
+impl std::error::Error for BinaryError {}
+
+fn write_bits(term: &DeBruijn, bits: &mut Vec<bool>) -> Result<(), BinaryError> {
+    match term {
+        DeBruijn::Abstraction(body) => {
+            bits.extend([false, false]);
+            write_bits(body, bits)
+        }
+        DeBruijn::Application(function, argument) => {
+            bits.extend([false, true]);
+            write_bits(function, bits)?;
+            write_bits(argument, bits)
+        }
+        // indices start at 1 in the encoding
+        DeBruijn::Index(index) => {
+            bits.extend(std::iter::repeat_n(true, index + 1));
+            bits.push(false);
+            Ok(())
+        }
+        DeBruijn::Free(name) => Err(BinaryError::FreeVariable(name.clone())),
+    }
+}
+
+/// Encodes the closed term `expr` in John Tromp's Binary Lambda Calculus:
+/// `00 M` for an abstraction, `01 M N` for an application and `1ⁱ0` for the
+/// variable with de Bruijn index `i`, counting from 1.
+pub fn encode(expr: &LambdaExpression) -> Result<Vec<bool>, BinaryError> {
+    let mut bits = vec![];
+    write_bits(&to_de_bruijn(expr), &mut bits)?;
+    Ok(bits)
+}
+
+/// The number of bits in the encoding of `expr`, a measure of how complex
+/// it is.
+pub fn bit_length(expr: &LambdaExpression) -> Result<usize, BinaryError> {
+    encode(expr).map(|bits| bits.len())
+}
+
+struct Reader<'a> {
+    bits: &'a [bool],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn next(&mut self) -> Result<bool, BinaryError> {
+        let bit = *self
+            .bits
+            .get(self.position)
+            .ok_or(BinaryError::UnexpectedEnd)?;
+        self.position += 1;
+        Ok(bit)
+    }
+
+    /// Reads a term nested under `depth` abstractions.
+    fn read(&mut self, depth: usize) -> Result<DeBruijn, BinaryError> {
+        let start = self.position;
+        match (self.next()?, self.next()?) {
+            (false, false) => Ok(DeBruijn::Abstraction(Box::new(self.read(depth + 1)?))),
+            (false, true) => {
+                let function = self.read(depth)?;
+                let argument = self.read(depth)?;
+                Ok(DeBruijn::Application(
+                    Box::new(function),
+                    Box::new(argument),
+                ))
+            }
+            (true, second) => {
+                let mut index = 1;
+                let mut bit = second;
+                while bit {
+                    index += 1;
+                    bit = self.next()?;
+                }
+                if index > depth {
+                    return Err(BinaryError::UnboundIndex {
+                        index,
+                        position: start,
+                    });
+                }
+                Ok(DeBruijn::Index(index - 1))
+            }
+        }
+    }
+}
+
+/// Decodes a term encoded by `encode`, binders are named after how deeply
+/// they are nested.
+pub fn decode(bits: &[bool]) -> Result<LambdaExpression, BinaryError> {
+    let mut reader = Reader { bits, position: 0 };
+    let term = reader.read(0)?;
+    if reader.position < bits.len() {
+        return Err(BinaryError::TrailingBits {
+            position: reader.position,
+        });
+    }
+    Ok(from_de_bruijn(&term))
+}
+
+/// `bits` written with `0` and `1`.
+pub fn to_bit_string(bits: &[bool]) -> String {
+    bits.iter()
+        .map(|&bit| if bit { '1' } else { '0' })
+        .collect()
+}
+
+/// Reads a string of `0` and `1`, whitespace between them is ignored.
+pub fn from_bit_string(source: &str) -> Result<Vec<bool>, BinaryError> {
+    source
+        .chars()
+        .enumerate()
+        .filter(|(_, character)| !character.is_whitespace())
+        .map(|(position, character)| match character {
+            '0' => Ok(false),
+            '1' => Ok(true),
+            _ => Err(BinaryError::InvalidCharacter {
+                character,
+                position,
+            }),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de_bruijn::alpha_eq;
+    use crate::parser::parse;
+
+    macro_rules! test_encode {
+        ($name:ident, $expr:expr, $expected:expr) => {
+            ::paste::paste! {
+                #[test]
+                fn [<test_encode_$name>]() {
+                    let bits = encode(&parse($expr).unwrap()).unwrap();
+                    assert_eq!(to_bit_string(&bits), $expected);
+                }
+            }
+        };
+    }
+
+    test_encode!(identity, "λx.x", "0010");
+    test_encode!(k, "λx.λy.x", "0000110");
+    test_encode!(s, "λx.λy.λz.x z (y z)", "00000001011110100111010");
+    test_encode!(zero, "0", "000010");
+    test_encode!(shadowing, "λx.λx.x", "000010");
+
+    #[test]
+    fn test_round_trip() {
+        for source in [
+            "λf.(λx.x x)(λx.f (x x))",
+            "λn.λf.λx.n(λg.λh.h(g f))(λu.x)(λu.u)",
+            "(λx.x x)(λx.x x)",
+            "42",
+        ] {
+            let expr = parse(source).unwrap();
+            let decoded = decode(&encode(&expr).unwrap()).unwrap();
+            assert!(alpha_eq(&decoded, &expr), "{}", decoded);
+        }
+    }
+
+    #[test]
+    fn test_bit_string() {
+        let bits = from_bit_string("0000 110").unwrap();
+        assert_eq!(to_bit_string(&bits), "0000110");
+        assert_eq!(decode(&bits), Ok(parse("λa.λb.a").unwrap()));
+        assert_eq!(
+            from_bit_string("0012"),
+            Err(BinaryError::InvalidCharacter {
+                character: '2',
+                position: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_bit_length() {
+        assert_eq!(
+            bit_length(&parse("λf.(λx.x x)(λx.f (x x))").unwrap()),
+            Ok(25)
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            encode(&parse("λx.y").unwrap()),
+            Err(BinaryError::FreeVariable("y".to_string()))
+        );
+        let decode_string = |source| decode(&from_bit_string(source).unwrap());
+        assert_eq!(decode_string("001"), Err(BinaryError::UnexpectedEnd));
+        assert_eq!(
+            decode_string("00110"),
+            Err(BinaryError::UnboundIndex {
+                index: 2,
+                position: 2
+            })
+        );
+        assert_eq!(
+            decode_string("00100"),
+            Err(BinaryError::TrailingBits { position: 4 })
+        );
+    }
+}
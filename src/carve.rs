@@ -0,0 +1,403 @@
+use std::f64::consts::PI;
+use std::fmt;
+
+use svg::node::element::path::Data;
+use svg::node::element::{Circle, Group, Path};
+
+use crate::geometry::{catmul_rom_curves, cubic_bezier, to_catmul_rom_spline, Point, Polyline};
+use crate::ogham::Ogham;
+
+/// How many straight pieces a curve of the stem is measured with.
+const SAMPLES: usize = 64;
+
+/// The line Ogham letters are carved along.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Stem {
+    /// a whole circle, clockwise from the top
+    Circle { center: Point, radius: f64 },
+    /// the Catmull–Rom spline through the points, see
+    /// `geometry::to_catmul_rom_spline`, it needs at least `MIN_SPLINE_POINTS`
+    Spline(Vec<Point>),
+}
+
+/// The first and last point of a spline only steer its ends, so it needs two
+/// more to run between.
+pub const MIN_SPLINE_POINTS: usize = 4;
+
+/// A spline stem with fewer than `MIN_SPLINE_POINTS` points.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StemError {
+    pub points: usize,
+}
+
+impl fmt::Display for StemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "a spline stem needs at least {} points, got {}",
+            MIN_SPLINE_POINTS, self.points
+        )
+    }
+}
+
+impl std::error::Error for StemError {}
+
+impl Stem {
+    pub fn polyline(&self) -> Result<Polyline, StemError> {
+        Ok(match self {
+            Stem::Circle { center, radius } => {
+                let samples = 4 * SAMPLES;
+                Polyline::new((0..=samples).map(|i| {
+                    let angle = 2.0 * PI * i as f64 / samples as f64 - PI / 2.0;
+                    (
+                        center.0 + radius * angle.cos(),
+                        center.1 + radius * angle.sin(),
+                    )
+                }))
+            }
+            Stem::Spline(points) if points.len() < MIN_SPLINE_POINTS => {
+                return Err(StemError {
+                    points: points.len(),
+                })
+            }
+            Stem::Spline(points) => {
+                Polyline::new(catmul_rom_curves(points).into_iter().flat_map(|curve| {
+                    (0..=SAMPLES).map(move |i| cubic_bezier(curve, i as f64 / SAMPLES as f64))
+                }))
+            }
+        })
+    }
+
+    fn to_path(&self) -> Path {
+        let data = match self {
+            Stem::Circle {
+                center: (x, y),
+                radius,
+            } => Data::new()
+                .move_to((*x, y - radius))
+                .elliptical_arc_to((*radius, *radius, 0, 0, 1, *x, y + radius))
+                .elliptical_arc_to((*radius, *radius, 0, 0, 1, *x, y - radius))
+                .close(),
+            Stem::Spline(points) => to_catmul_rom_spline(Data::new(), points),
+        };
+        Path::new().set("d", data)
+    }
+}
+
+/// Sizes of carved letters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CarveOptions {
+    /// how far a consonant score reaches from the stem
+    pub score: f64,
+    /// distance between the scores of one letter
+    pub spacing: f64,
+    /// space between two letters
+    pub letter_gap: f64,
+    /// space left for `Ogham::Space`
+    pub word_gap: f64,
+    /// angle between the scores of the third aicme and the stem, in radians
+    pub slant: f64,
+    pub stroke_width: f64,
+}
+
+impl Default for CarveOptions {
+    fn default() -> Self {
+        CarveOptions {
+            score: 6.0,
+            spacing: 2.5,
+            letter_gap: 4.0,
+            word_gap: 6.0,
+            slant: PI / 3.0,
+            stroke_width: 1.0,
+        }
+    }
+}
+
+/// A stroke of a letter. Points are given as the distance along the stem from
+/// where the stroke is, and the distance to the left of the stem, the side
+/// the second aicme is on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mark {
+    Line { at: f64, from: Point, to: Point },
+    Loop { at: f64, center: Point, radius: f64 },
+}
+
+/// The strokes of `letter`, placed from 0, and how far along the stem the
+/// letter reaches.
+fn marks(letter: Ogham, options: &CarveOptions) -> (Vec<Mark>, f64) {
+    let score = options.score;
+    let notch = score * 0.4;
+    let half = score / 2.0;
+    let line = |at, from, to| Mark::Line { at, from, to };
+    // scores at the same angle, `count` of them
+    let scores = |count: usize, from: Point, to: Point| {
+        let lean = from.0.abs().max(to.0.abs());
+        let marks = (0..count)
+            .map(|i| line(lean + i as f64 * options.spacing, from, to))
+            .collect();
+        (marks, 2.0 * lean + (count - 1) as f64 * options.spacing)
+    };
+    let lean = score / options.slant.tan();
+
+    match letter {
+        // the first aicme on the right of the stem, the second on the left,
+        // the third across it at a slant and the vowels as short notches
+        Ogham::B => scores(1, (0.0, 0.0), (0.0, -score)),
+        Ogham::L => scores(2, (0.0, 0.0), (0.0, -score)),
+        Ogham::F => scores(3, (0.0, 0.0), (0.0, -score)),
+        Ogham::S => scores(4, (0.0, 0.0), (0.0, -score)),
+        Ogham::N => scores(5, (0.0, 0.0), (0.0, -score)),
+        Ogham::H => scores(1, (0.0, 0.0), (0.0, score)),
+        Ogham::D => scores(2, (0.0, 0.0), (0.0, score)),
+        Ogham::T => scores(3, (0.0, 0.0), (0.0, score)),
+        Ogham::C => scores(4, (0.0, 0.0), (0.0, score)),
+        Ogham::Q => scores(5, (0.0, 0.0), (0.0, score)),
+        Ogham::M => scores(1, (-lean, -score), (lean, score)),
+        Ogham::G => scores(2, (-lean, -score), (lean, score)),
+        Ogham::NG => scores(3, (-lean, -score), (lean, score)),
+        Ogham::Z => scores(4, (-lean, -score), (lean, score)),
+        Ogham::R => scores(5, (-lean, -score), (lean, score)),
+        Ogham::A => scores(1, (0.0, -notch), (0.0, notch)),
+        Ogham::O => scores(2, (0.0, -notch), (0.0, notch)),
+        Ogham::U => scores(3, (0.0, -notch), (0.0, notch)),
+        Ogham::E => scores(4, (0.0, -notch), (0.0, notch)),
+        Ogham::I => scores(5, (0.0, -notch), (0.0, notch)),
+
+        // a cross
+        Ogham::EA => (
+            vec![
+                line(half, (-half, -half), (half, half)),
+                line(half, (-half, half), (half, -half)),
+            ],
+            score,
+        ),
+        // a lozenge
+        Ogham::OI => (
+            vec![
+                line(half, (-half, 0.0), (0.0, half)),
+                line(half, (0.0, half), (half, 0.0)),
+                line(half, (half, 0.0), (0.0, -half)),
+                line(half, (0.0, -half), (-half, 0.0)),
+            ],
+            score,
+        ),
+        // a loop on the left
+        Ogham::UI => (
+            vec![Mark::Loop {
+                at: half,
+                center: (0.0, half),
+                radius: half,
+            }],
+            score,
+        ),
+        // a grid across
+        Ogham::IA => (
+            vec![
+                line(half, (-half / 2.0, -half), (-half / 2.0, half)),
+                line(half, (half / 2.0, -half), (half / 2.0, half)),
+                line(half, (-half, -half / 2.0), (half, -half / 2.0)),
+                line(half, (-half, half / 2.0), (half, half / 2.0)),
+            ],
+            score,
+        ),
+        // two crosses
+        Ogham::AE => (
+            vec![
+                line(half, (-half, -half), (half, half)),
+                line(half, (-half, half), (half, -half)),
+                line(score + half, (-half, -half), (half, half)),
+                line(score + half, (-half, half), (half, -half)),
+            ],
+            2.0 * score,
+        ),
+        // a point hanging on the right
+        Ogham::P => (
+            vec![
+                line(half, (-half, 0.0), (0.0, -score)),
+                line(half, (0.0, -score), (half, 0.0)),
+            ],
+            score,
+        ),
+        // the feather that starts a line points along it, the one that ends
+        // it points back
+        Ogham::Start => (
+            vec![
+                line(0.0, (0.0, -half), (half, 0.0)),
+                line(0.0, (0.0, half), (half, 0.0)),
+            ],
+            half,
+        ),
+        Ogham::End => (
+            vec![
+                line(0.0, (0.0, 0.0), (half, -half)),
+                line(0.0, (0.0, 0.0), (half, half)),
+            ],
+            half,
+        ),
+        Ogham::Space => (vec![], options.word_gap),
+    }
+}
+
+/// How many of `letters`, from the first, fit on a stem `length` long.
+pub fn fitting(letters: &[Ogham], length: f64, options: &CarveOptions) -> usize {
+    let mut end = 0.0;
+    letters
+        .iter()
+        .take_while(|&&letter| {
+            end += marks(letter, options).1;
+            let fits = end <= length;
+            end += options.letter_gap;
+            fits
+        })
+        .count()
+}
+
+/// `letters` drawn as strokes along `stem`, the stem itself drawn as one
+/// continuous line, so they join up however it bends. The first aicme is on
+/// the right of the stem, looking the way it runs, so around a circle it is
+/// inside. Letters that don't fit on the stem are left out, see `fitting`,
+/// so it also returns how many were carved.
+pub fn carve(
+    letters: &[Ogham],
+    stem: &Stem,
+    options: &CarveOptions,
+) -> Result<(Group, usize), StemError> {
+    let line = stem.polyline()?;
+    let count = fitting(letters, line.length(), options);
+    // a point given along and to the left of the stem at `distance`
+    let place = |distance: f64, (along, left): Point| {
+        let ((x, y), (dx, dy)) = line.at(distance);
+        (x + dx * along + dy * left, y + dy * along - dx * left)
+    };
+
+    let mut data = Data::new();
+    let mut loops = vec![];
+    let mut start = 0.0;
+    for &letter in &letters[..count] {
+        let (marks, width) = marks(letter, options);
+        for mark in marks {
+            match mark {
+                Mark::Line { at, from, to } => {
+                    data = data
+                        .move_to(place(start + at, from))
+                        .line_to(place(start + at, to));
+                }
+                Mark::Loop { at, center, radius } => {
+                    let (cx, cy) = place(start + at, center);
+                    loops.push(Circle::new().set("cx", cx).set("cy", cy).set("r", radius));
+                }
+            }
+        }
+        start += width + options.letter_gap;
+    }
+
+    let mut group = Group::new()
+        .set("fill", "none")
+        .set("stroke", "black")
+        .set("stroke-width", options.stroke_width)
+        .set("stroke-linecap", "round")
+        .add(stem.to_path())
+        .add(Path::new().set("d", data));
+    for circle in loops {
+        group = group.add(circle);
+    }
+    Ok((group, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ogham::transliterate;
+    use svg::node::element::path::Command;
+
+    /// The ends of every stroke in `group`, rounded to hundredths.
+    fn strokes(group: &Group) -> Vec<(Point, Point)> {
+        let round = |value: f32| (value as f64 * 100.0).round() / 100.0;
+        let text = group.to_string();
+        let d = text
+            .split("d=\"")
+            .nth(2)
+            .unwrap()
+            .split('"')
+            .next()
+            .unwrap();
+        let data = Data::parse(d).unwrap();
+        data.iter()
+            .collect::<Vec<_>>()
+            .chunks(2)
+            .map(|pair| match pair {
+                [Command::Move(_, from), Command::Line(_, to)] => (
+                    (round(from[0]), round(from[1])),
+                    (round(to[0]), round(to[1])),
+                ),
+                _ => panic!("unexpected commands {:?}", pair),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sides() {
+        let stem = Stem::Spline(vec![(-10.0, 0.0), (0.0, 0.0), (100.0, 0.0), (110.0, 0.0)]);
+        let options = CarveOptions::default();
+        // running right, the first aicme hangs below and the second stands
+        // above, y grows downwards
+        assert_eq!(
+            strokes(&carve(&[Ogham::B, Ogham::H], &stem, &options).unwrap().0),
+            vec![((0.0, 0.0), (0.0, 6.0)), ((4.0, 0.0), (4.0, -6.0))]
+        );
+        assert_eq!(
+            strokes(&carve(&[Ogham::L], &stem, &options).unwrap().0),
+            vec![((0.0, 0.0), (0.0, 6.0)), ((2.5, 0.0), (2.5, 6.0))]
+        );
+        // the third aicme leans forward across the stem
+        let lean = (2.0 * 6.0 / (PI / 3.0).tan() * 100.0).round() / 100.0;
+        assert_eq!(
+            strokes(&carve(&[Ogham::M], &stem, &options).unwrap().0),
+            vec![((0.0, 6.0), (lean, -6.0))]
+        );
+    }
+
+    #[test]
+    fn test_circle() {
+        let stem = Stem::Circle {
+            center: (0.0, 0.0),
+            radius: 50.0,
+        };
+        // the first score of `b` points from the top of the circle inwards
+        let (group, carved) = carve(&[Ogham::B], &stem, &CarveOptions::default()).unwrap();
+        assert_eq!(carved, 1);
+        assert_eq!(strokes(&group), vec![((0.0, -50.0), (0.0, -44.0))]);
+        assert!(group.to_string().contains("A50,50,0,0,1,0,50"));
+        assert!(!group.to_string().contains("<text"));
+    }
+
+    #[test]
+    fn test_fitting() {
+        let options = CarveOptions::default();
+        let letters = transliterate("bl a");
+        // `b` ends at 0, `l` at 4 + 2.5, the space 4 + 6 later and `a`
+        // after another 4
+        assert_eq!(fitting(&letters, 20.5, &options), 4);
+        assert_eq!(fitting(&letters, 20.0, &options), 3);
+        assert_eq!(fitting(&letters, 6.5, &options), 2);
+        let stem = Stem::Spline(vec![(-1.0, 0.0), (0.0, 0.0), (10.0, 0.0), (11.0, 0.0)]);
+        // only `b` and `l` fit on a stem 10 long, the rest is left out
+        let (group, carved) = carve(&letters, &stem, &options).unwrap();
+        assert_eq!(carved, 2);
+        assert_eq!(strokes(&group).len(), 3);
+    }
+
+    #[test]
+    fn test_spline_needs_four_points() {
+        let points = [(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (3.0, 0.0)];
+        for count in 0..MIN_SPLINE_POINTS {
+            let stem = Stem::Spline(points[..count].to_vec());
+            assert_eq!(
+                carve(&[Ogham::B], &stem, &CarveOptions::default()).unwrap_err(),
+                StemError { points: count }
+            );
+        }
+        let stem = Stem::Spline(points.to_vec());
+        assert!(carve(&[Ogham::B], &stem, &CarveOptions::default()).is_ok());
+    }
+}
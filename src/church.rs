@@ -0,0 +1,125 @@
+use std::fmt;
+
+use crate::de_bruijn::{to_de_bruijn, DeBruijn};
+use crate::ast::LambdaExpression;
+
+/// The Church numeral for `n`, `λf.λx.f (f (... (f x)))` with `n`
+/// applications of `f`.
+pub fn numeral(n: usize) -> LambdaExpression {
+    let body = (0..n).fold(LambdaExpression::Variable("x".to_string()), |body, _| {
+        LambdaExpression::Application(
+            Box::new(LambdaExpression::Variable("f".to_string())),
+            Box::new(body),
+        )
+    });
+    LambdaExpression::Abstraction(
+        "f".to_string(),
+        Box::new(LambdaExpression::Abstraction("x".to_string(), Box::new(body))),
+    )
+}
+
+/// The body of a term of the form `λ.λ.body`.
+fn two_binder_body(term: &DeBruijn) -> Option<&DeBruijn> {
+    match term {
+        DeBruijn::Abstraction(inner) => match inner.as_ref() {
+            DeBruijn::Abstraction(body) => Some(body),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The number `expr` is the Church numeral of, whatever its binders are
+/// named.
+pub fn to_numeral(expr: &LambdaExpression) -> Option<usize> {
+    let term = to_de_bruijn(expr);
+    let mut body = two_binder_body(&term)?;
+    let mut n = 0;
+    // `f` is the outer binder, so index 1, and `x` the inner one, index 0
+    loop {
+        match body {
+            DeBruijn::Index(0) => return Some(n),
+            DeBruijn::Application(function, argument) if **function == DeBruijn::Index(1) => {
+                n += 1;
+                body = argument;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// The boolean `expr` is the Church encoding of, `λt.λf.t` is `true` and
+/// `λt.λf.f` is `false`.
+pub fn to_boolean(expr: &LambdaExpression) -> Option<bool> {
+    match two_binder_body(&to_de_bruijn(expr))? {
+        DeBruijn::Index(1) => Some(true),
+        DeBruijn::Index(0) => Some(false),
+        _ => None,
+    }
+}
+
+/// A value recognized in a term.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Value {
+    Numeral(usize),
+    Boolean(bool),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Numeral(n) => write!(f, "{}", n),
+            Value::Boolean(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+/// Tells what value `expr` encodes, if any. `0` and `false` are the same
+/// term, it is reported as `0`.
+pub fn recognize(expr: &LambdaExpression) -> Option<Value> {
+    to_numeral(expr)
+        .map(Value::Numeral)
+        .or_else(|| to_boolean(expr).map(Value::Boolean))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn test_numeral() {
+        assert_eq!(numeral(0), parse("λf.λx.x").unwrap());
+        assert_eq!(numeral(4), parse("λf.λx.f(f(f(f x)))").unwrap());
+    }
+
+    #[test]
+    fn test_to_numeral() {
+        for n in [0, 1, 2, 42] {
+            assert_eq!(to_numeral(&numeral(n)), Some(n));
+        }
+        assert_eq!(to_numeral(&parse("λs.λz.s (s z)").unwrap()), Some(2));
+        assert_eq!(to_numeral(&parse("λf.λf.f").unwrap()), Some(0));
+        assert_eq!(to_numeral(&parse("λf.λx.x f").unwrap()), None);
+        assert_eq!(to_numeral(&parse("λf.λx.f (g x)").unwrap()), None);
+        assert_eq!(to_numeral(&parse("λf.f").unwrap()), None);
+    }
+
+    #[test]
+    fn test_to_boolean() {
+        assert_eq!(to_boolean(&parse("λa.λb.a").unwrap()), Some(true));
+        assert_eq!(to_boolean(&parse("λa.λb.b").unwrap()), Some(false));
+        assert_eq!(to_boolean(&parse("λa.λa.a").unwrap()), Some(false));
+        assert_eq!(to_boolean(&parse("λa.λb.a b").unwrap()), None);
+    }
+
+    #[test]
+    fn test_recognize() {
+        assert_eq!(recognize(&numeral(3)), Some(Value::Numeral(3)));
+        assert_eq!(recognize(&parse("λt.λf.t").unwrap()), Some(Value::Boolean(true)));
+        assert_eq!(recognize(&parse("λt.λf.f").unwrap()), Some(Value::Numeral(0)));
+        assert_eq!(recognize(&parse("λx.x").unwrap()), None);
+        assert_eq!(Value::Boolean(true).to_string(), "true");
+        assert_eq!(Value::Numeral(42).to_string(), "42");
+    }
+}
@@ -0,0 +1,532 @@
+use std::ffi::OsString;
+use std::fmt;
+use std::path::PathBuf;
+
+use magic_circuit::combinators::Basis;
+use magic_circuit::evaluator::Strategy;
+use magic_circuit::fonts::{FontConfig, FontSource};
+use magic_circuit::ogham::OghamScript;
+use magic_circuit::render::{ImageSize, Style};
+use magic_circuit::script::{self, Script, Unmappable, SCRIPTS};
+
+pub const USAGE: &str = "\
+usage: magic_circuit <command> [options]
+
+commands:
+  render [term|file]     draw a term as SVG
+  png [term|file]        draw a term as PNG
+  eval [term|file]       reduce a term and print its normal form
+  trace [term|file]      draw every reduction step as numbered SVG and PNG
+                         frames into the `--output` directory, `trace` by
+                         default
+  transliterate [text]   write text in another script
+  demo                   draw the demo images into the current directory
+
+a term is read from the file of that name if there is one, from standard
+input when it is left out or `-`. Names from the prelude can be used and
+files can start with `let name = term;` definitions. Abstractions are written
+`λx.` or `\\x.`, `\\x y.` is short for `λx.λy.`, and so is `λxy.` unless the
+body uses the name `xy` rather than `x` or `y`.
+
+options:
+  -o, --output <file>    where to write the drawing, standard output by default
+  --style <style>        `circles` (default), `tromp`, or the combinators of
+                         `ski` or `skibc` with a circle around applications
+  --bits                 draw the binary encoding around the circles
+  --types                write the type of every abstraction above it, names
+                         of definitions are drawn unexpanded and typed apart
+  --inscribe <text>      write text around the circles, in `--script`
+  --carve                draw an Ogham inscription as strokes, without a font
+  --font <file|family>   write text in this font, a file or a family of the
+                         system or the bundled Tengwar Annatar
+  --embed-font           inline the font into the SVG so it shows anywhere
+  --subset-font          inline only the glyphs the drawing uses
+  --no-system-fonts      only load the bundled fonts and `--font`
+  --width <pixels>       width of the PNG
+  --dpi <dpi>            resolution of the PNG, 96 by default
+  --strategy <strategy>  `normal` (default), `applicative`, `name`, `value`
+                         or `head`
+  --steps <count>        reductions before giving up, 1000 by default, 100
+                         for `trace`
+  --script <script>      `ogham` (default), `tengwar`, `futhark` or `theban`
+  --unmappable <policy>  what to write for characters the script has no letter
+                         for: `space` (default), `drop`, `digits` to spell
+                         digits out, `diacritics` to strip accents or `error`
+  --reverse              read Ogham back as Latin
+  --losses               list the characters that don't read back the same
+                         from Ogham
+  -h, --help             print this message
+
+exits with 1 on errors, 2 on invalid arguments and 3 when `eval` finds no
+normal form.
+";
+
+const DEFAULT_STEPS: usize = 1000;
+/// Every step of a trace is a frame, so it gives up much sooner.
+const DEFAULT_TRACE_STEPS: usize = 100;
+
+/// Where a term is read from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Input {
+    Stdin,
+    File(PathBuf),
+    Term(String),
+}
+
+/// What to draw and how, shared by `render` and `png`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Drawing {
+    pub input: Input,
+    /// `None` writes to standard output
+    pub output: Option<PathBuf>,
+    pub style: Style,
+    pub bit_ring: bool,
+    pub types: bool,
+    /// text written around the circles and the script it is written in
+    pub inscription: Option<(String, &'static dyn Script)>,
+    pub unmappable: Unmappable,
+    /// the inscription is carved as strokes instead of written in a font
+    pub carve: bool,
+    pub fonts: FontConfig,
+    pub embed_font: EmbedFont,
+}
+
+/// Whether the font is inlined into the SVG.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EmbedFont {
+    /// the font is only referred to by its family
+    #[default]
+    No,
+    Full,
+    /// only the glyphs of the characters in the drawing are inlined
+    Subset,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Render(Drawing),
+    Png(Drawing, ImageSize),
+    Eval {
+        input: Input,
+        strategy: Strategy,
+        max_steps: usize,
+    },
+    Trace {
+        input: Input,
+        /// the directory the frames are written to
+        output: PathBuf,
+        strategy: Strategy,
+        max_steps: usize,
+    },
+    Transliterate {
+        script: &'static dyn Script,
+        /// `None` reads standard input
+        text: Option<String>,
+        unmappable: Unmappable,
+        reverse: bool,
+        losses: bool,
+    },
+    Demo,
+    Help,
+}
+
+/// The arguments make no sense, the message says why.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UsageError(pub String);
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for UsageError {}
+
+impl From<pico_args::Error> for UsageError {
+    fn from(error: pico_args::Error) -> Self {
+        UsageError(error.to_string())
+    }
+}
+
+fn parse_style(value: &str) -> Result<Style, String> {
+    match value {
+        "circles" => Ok(Style::Circles),
+        "tromp" => Ok(Style::Tromp),
+        "ski" => Ok(Style::Combinators(Basis::Ski)),
+        "skibc" => Ok(Style::Combinators(Basis::SkiBc)),
+        _ => Err("expected `circles`, `tromp`, `ski` or `skibc`".to_string()),
+    }
+}
+
+fn parse_strategy(value: &str) -> Result<Strategy, String> {
+    match value {
+        "normal" => Ok(Strategy::NormalOrder),
+        "applicative" => Ok(Strategy::ApplicativeOrder),
+        "name" => Ok(Strategy::CallByName),
+        "value" => Ok(Strategy::CallByValue),
+        "head" => Ok(Strategy::HeadReduction),
+        _ => Err("expected `normal`, `applicative`, `name`, `value` or `head`".to_string()),
+    }
+}
+
+fn parse_script(value: &str) -> Result<&'static dyn Script, String> {
+    script::by_name(value).ok_or_else(|| {
+        let names: Vec<String> = SCRIPTS
+            .iter()
+            .map(|script| format!("`{}`", script.name()))
+            .collect();
+        format!("expected one of {}", names.join(", "))
+    })
+}
+
+fn parse_unmappable(value: &str) -> Result<Unmappable, String> {
+    match value {
+        "drop" => Ok(Unmappable::Drop),
+        "space" => Ok(Unmappable::Space),
+        "digits" => Ok(Unmappable::SpellDigits),
+        "diacritics" => Ok(Unmappable::StripDiacritics),
+        "error" => Ok(Unmappable::Error),
+        _ => Err("expected `drop`, `space`, `digits`, `diacritics` or `error`".to_string()),
+    }
+}
+
+/// The arguments left after every option was taken, an unknown option among
+/// them is an error.
+fn free_arguments(args: pico_args::Arguments) -> Result<Vec<String>, UsageError> {
+    args.finish()
+        .into_iter()
+        .map(|arg| match arg.into_string() {
+            Ok(arg) if arg.starts_with('-') && arg != "-" => {
+                Err(UsageError(format!("unknown option `{}`", arg)))
+            }
+            Ok(arg) => Ok(arg),
+            Err(arg) => Err(UsageError(format!(
+                "`{}` is not valid UTF-8",
+                arg.to_string_lossy()
+            ))),
+        })
+        .collect()
+}
+
+/// `-` and no argument at all read standard input, an existing file is read
+/// from disk and anything else is taken as the term itself.
+fn input(free: Vec<String>) -> Result<Input, UsageError> {
+    match <[String; 1]>::try_from(free) {
+        Ok([arg]) if arg == "-" => Ok(Input::Stdin),
+        Ok([arg]) if PathBuf::from(&arg).is_file() => Ok(Input::File(arg.into())),
+        Ok([arg]) => Ok(Input::Term(arg)),
+        Err(free) if free.is_empty() => Ok(Input::Stdin),
+        Err(free) => Err(UsageError(format!(
+            "expected one term or file, got {}, quote terms that contain spaces",
+            free.len()
+        ))),
+    }
+}
+
+/// Reads the options shared by `render` and `png` and the term to draw, so
+/// the options only `png` has must be taken before.
+fn drawing(mut args: pico_args::Arguments) -> Result<Drawing, UsageError> {
+    let output = args.opt_value_from_os_str(["-o", "--output"], |value| {
+        Ok::<_, String>(PathBuf::from(value))
+    })?;
+    let style = args.opt_value_from_fn("--style", parse_style)?;
+    let bit_ring = args.contains("--bits");
+    let types = args.contains("--types");
+    let inscription: Option<String> = args.opt_value_from_str("--inscribe")?;
+    let script = args.opt_value_from_fn("--script", parse_script)?;
+    let unmappable = args.opt_value_from_fn("--unmappable", parse_unmappable)?;
+    let carve = args.contains("--carve");
+    if inscription.is_none() && (script.is_some() || unmappable.is_some() || carve) {
+        return Err(UsageError(
+            "`--script`, `--unmappable` and `--carve` need an `--inscribe`".to_string(),
+        ));
+    }
+    let script = script.unwrap_or(&OghamScript);
+    if carve && script.name() != OghamScript.name() {
+        return Err(UsageError("only `ogham` can be carved".to_string()));
+    }
+    let font: Option<String> = args.opt_value_from_str("--font")?;
+    let system_fonts = !args.contains("--no-system-fonts");
+    let embed_font = match (
+        args.contains("--embed-font"),
+        args.contains("--subset-font"),
+    ) {
+        (_, true) => EmbedFont::Subset,
+        (true, false) => EmbedFont::Full,
+        (false, false) => EmbedFont::No,
+    };
+    if font.is_none() && embed_font != EmbedFont::No {
+        return Err(UsageError(
+            "`--embed-font` and `--subset-font` need a `--font`".to_string(),
+        ));
+    }
+    let fonts = FontConfig::new().system_fonts(system_fonts);
+    Ok(Drawing {
+        input: input(free_arguments(args)?)?,
+        output: output.filter(|path| path.as_os_str() != "-"),
+        style: style.unwrap_or_default(),
+        bit_ring,
+        types,
+        inscription: inscription.map(|text| (text, script)),
+        unmappable: unmappable.unwrap_or_default(),
+        carve,
+        fonts: match font {
+            Some(font) => fonts.font(FontSource::guess(&font)),
+            None => fonts,
+        },
+        embed_font,
+    })
+}
+
+pub fn parse_args(args: Vec<OsString>) -> Result<Command, UsageError> {
+    let mut args = pico_args::Arguments::from_vec(args);
+    if args.contains(["-h", "--help"]) {
+        return Ok(Command::Help);
+    }
+    let subcommand = args
+        .subcommand()?
+        .ok_or_else(|| UsageError("expected a command".to_string()))?;
+    match subcommand.as_str() {
+        "render" => Ok(Command::Render(drawing(args)?)),
+        "png" => {
+            let width: Option<u32> = args.opt_value_from_str("--width")?;
+            let dpi: Option<f32> = args.opt_value_from_str("--dpi")?;
+            let size = match (width, dpi) {
+                (Some(_), Some(_)) => {
+                    return Err(UsageError(
+                        "`--width` and `--dpi` can't be used together".to_string(),
+                    ))
+                }
+                (Some(0), None) => return Err(UsageError("`--width` can't be 0".to_string())),
+                (Some(width), None) => ImageSize::Width(width),
+                (None, Some(dpi)) if dpi > 0.0 => ImageSize::Scale(dpi / 96.0),
+                (None, Some(_)) => {
+                    return Err(UsageError("`--dpi` has to be positive".to_string()))
+                }
+                (None, None) => ImageSize::Natural,
+            };
+            Ok(Command::Png(drawing(args)?, size))
+        }
+        "eval" => {
+            let strategy = args.opt_value_from_fn("--strategy", parse_strategy)?;
+            let max_steps = args.opt_value_from_str("--steps")?;
+            Ok(Command::Eval {
+                input: input(free_arguments(args)?)?,
+                strategy: strategy.unwrap_or(Strategy::NormalOrder),
+                max_steps: max_steps.unwrap_or(DEFAULT_STEPS),
+            })
+        }
+        "trace" => {
+            let output = args.opt_value_from_os_str(["-o", "--output"], |value| {
+                Ok::<_, String>(PathBuf::from(value))
+            })?;
+            let strategy = args.opt_value_from_fn("--strategy", parse_strategy)?;
+            let max_steps = args.opt_value_from_str("--steps")?;
+            Ok(Command::Trace {
+                input: input(free_arguments(args)?)?,
+                output: output.unwrap_or_else(|| PathBuf::from("trace")),
+                strategy: strategy.unwrap_or(Strategy::NormalOrder),
+                max_steps: max_steps.unwrap_or(DEFAULT_TRACE_STEPS),
+            })
+        }
+        "transliterate" => {
+            let script = args.opt_value_from_fn("--script", parse_script)?;
+            let unmappable = args.opt_value_from_fn("--unmappable", parse_unmappable)?;
+            let reverse = args.contains("--reverse");
+            let losses = args.contains("--losses");
+            let script = script.unwrap_or(&OghamScript);
+            if (reverse || losses) && script.name() != OghamScript.name() {
+                return Err(UsageError(
+                    "`--reverse` and `--losses` only work with `ogham`".to_string(),
+                ));
+            }
+            let free = free_arguments(args)?;
+            Ok(Command::Transliterate {
+                script,
+                text: match free.as_slice() {
+                    [] => None,
+                    [stdin] if stdin == "-" => None,
+                    _ => Some(free.join(" ")),
+                },
+                unmappable: unmappable.unwrap_or_default(),
+                reverse,
+                losses,
+            })
+        }
+        "demo" => match free_arguments(args)?.first() {
+            Some(arg) => Err(UsageError(format!("unexpected argument `{}`", arg))),
+            None => Ok(Command::Demo),
+        },
+        _ => Err(UsageError(format!("unknown command `{}`", subcommand))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, UsageError> {
+        parse_args(args.iter().map(OsString::from).collect())
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            parse(&["render", "λx.x", "-o", "out.svg", "--style", "tromp"]),
+            Ok(Command::Render(Drawing {
+                input: Input::Term("λx.x".to_string()),
+                output: Some("out.svg".into()),
+                style: Style::Tromp,
+                bit_ring: false,
+                types: false,
+                inscription: None,
+                unmappable: Unmappable::Space,
+                carve: false,
+                fonts: FontConfig::new(),
+                embed_font: EmbedFont::No,
+            }))
+        );
+        assert_eq!(
+            parse(&["render", "λx.x", "--style", "skibc"]).map(|command| match command {
+                Command::Render(drawing) => drawing.style,
+                _ => unreachable!(),
+            }),
+            Ok(Style::Combinators(Basis::SkiBc))
+        );
+        assert_eq!(
+            parse(&["render", "--bits", "-o", "-"]),
+            Ok(Command::Render(Drawing {
+                input: Input::Stdin,
+                output: None,
+                style: Style::Circles,
+                bit_ring: true,
+                types: false,
+                inscription: None,
+                unmappable: Unmappable::Space,
+                carve: false,
+                fonts: FontConfig::new(),
+                embed_font: EmbedFont::No,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_fonts() {
+        let drawing = |args: &[&str]| match parse(args) {
+            Ok(Command::Render(drawing)) => Ok(drawing),
+            Ok(command) => panic!("parsed as {:?}", command),
+            Err(err) => Err(err),
+        };
+        let subset = drawing(&["render", "I", "--font", "Tengwar Annatar", "--subset-font"]);
+        assert_eq!(
+            subset.map(|drawing| (drawing.fonts, drawing.embed_font)),
+            Ok((
+                FontConfig::new().font(FontSource::Family("Tengwar Annatar".to_string())),
+                EmbedFont::Subset
+            ))
+        );
+        assert!(drawing(&["render", "I", "--embed-font"]).is_err());
+    }
+
+    #[test]
+    fn test_inscription() {
+        let inscription = |args: &[&str]| match parse(args) {
+            Ok(Command::Render(drawing)) => Ok(drawing.inscription),
+            Ok(command) => panic!("parsed as {:?}", command),
+            Err(err) => Err(err),
+        };
+        assert_eq!(
+            inscription(&["render", "I", "--inscribe", "identity"]),
+            Ok(Some(("identity".to_string(), &OghamScript as &dyn Script)))
+        );
+        assert_eq!(
+            inscription(&["render", "I", "--inscribe", "I", "--script", "tengwar"])
+                .map(|inscription| inscription.map(|(_, script)| script.name())),
+            Ok(Some("tengwar"))
+        );
+        assert!(inscription(&["render", "I", "--script", "tengwar"]).is_err());
+        assert!(inscription(&["render", "I", "--unmappable", "drop"]).is_err());
+        assert!(matches!(
+            parse(&["render", "I", "--inscribe", "I", "--carve"]),
+            Ok(Command::Render(Drawing { carve: true, .. }))
+        ));
+        assert!(parse(&["render", "I", "--carve"]).is_err());
+        assert!(parse(&["render", "I", "--inscribe", "I", "--script", "theban", "--carve"]).is_err());
+        assert!(inscription(&["render", "I", "--inscribe", "I", "--script", "cirth"]).is_err());
+    }
+
+    #[test]
+    fn test_png_size() {
+        let size = |args: &[&str]| match parse(args) {
+            Ok(Command::Png(_, size)) => Ok(size),
+            Ok(command) => panic!("parsed as {:?}", command),
+            Err(err) => Err(err),
+        };
+        assert_eq!(
+            size(&["png", "I", "--width", "800"]),
+            Ok(ImageSize::Width(800))
+        );
+        assert_eq!(
+            size(&["png", "I", "--dpi", "192"]),
+            Ok(ImageSize::Scale(2.0))
+        );
+        assert!(size(&["png", "I", "--width", "800", "--dpi", "192"]).is_err());
+        assert!(size(&["png", "I", "--width", "wide"]).is_err());
+    }
+
+    #[test]
+    fn test_eval_and_transliterate() {
+        assert_eq!(
+            parse(&["eval", "PRED two", "--strategy", "head", "--steps", "5"]),
+            Ok(Command::Eval {
+                input: Input::Term("PRED two".to_string()),
+                strategy: Strategy::HeadReduction,
+                max_steps: 5,
+            })
+        );
+        assert_eq!(
+            parse(&["trace", "PRED two", "-o", "frames"]),
+            Ok(Command::Trace {
+                input: Input::Term("PRED two".to_string()),
+                output: PathBuf::from("frames"),
+                strategy: Strategy::NormalOrder,
+                max_steps: DEFAULT_TRACE_STEPS,
+            })
+        );
+        assert_eq!(
+            parse(&["transliterate", "hello", "world"]),
+            Ok(Command::Transliterate {
+                script: &OghamScript,
+                text: Some("hello world".to_string()),
+                unmappable: Unmappable::Space,
+                reverse: false,
+                losses: false,
+            })
+        );
+        assert!(parse(&["transliterate", "--script", "theban", "--reverse", "x"]).is_err());
+        assert!(matches!(
+            parse(&["transliterate", "--unmappable", "digits", "route 66"]),
+            Ok(Command::Transliterate {
+                unmappable: Unmappable::SpellDigits,
+                ..
+            })
+        ));
+        assert!(parse(&["transliterate", "--unmappable", "ignore", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_usage_errors() {
+        assert_eq!(
+            parse(&["draw"]),
+            Err(UsageError("unknown command `draw`".to_string()))
+        );
+        assert_eq!(
+            parse(&["render", "--colour", "red"]),
+            Err(UsageError("unknown option `--colour`".to_string()))
+        );
+        assert!(parse(&["render", "λx.x", "y"]).is_err());
+        assert!(parse(&["eval", "--strategy", "lazy"]).is_err());
+        assert!(parse(&[]).is_err());
+        assert_eq!(parse(&["demo", "--help"]), Ok(Command::Help));
+    }
+}
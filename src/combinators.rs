@@ -0,0 +1,495 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use svg::node::element::{Circle, Group, Text};
+use svg::Document;
+
+use crate::evaluator::Outcome;
+use crate::ast::LambdaExpression;
+use crate::parser::parse;
+use crate::layout::LayoutOptions;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Combinator {
+    /// `S x y z = x z (y z)`
+    S,
+    /// `K x y = x`
+    K,
+    /// `I x = x`
+    I,
+    /// `B x y z = x (y z)`
+    B,
+    /// `C x y z = x z y`
+    C,
+}
+
+impl Combinator {
+    /// The number of arguments it takes before it can be contracted.
+    pub fn arity(self) -> usize {
+        match self {
+            Combinator::I => 1,
+            Combinator::K => 2,
+            Combinator::S | Combinator::B | Combinator::C => 3,
+        }
+    }
+
+    /// The lambda term it stands for.
+    pub fn to_lambda(self) -> LambdaExpression {
+        let source = match self {
+            Combinator::S => "λx.λy.λz.x z (y z)",
+            Combinator::K => "λx.λy.x",
+            Combinator::I => "λx.x",
+            Combinator::B => "λx.λy.λz.x (y z)",
+            Combinator::C => "λx.λy.λz.x z y",
+        };
+        parse(source).expect("combinators are valid lambda terms")
+    }
+
+    /// Contracts the combinator applied to exactly `arity` arguments.
+    fn contract(self, arguments: &[CombinatorTerm]) -> CombinatorTerm {
+        let [x, rest @ ..] = arguments else {
+            unreachable!("every combinator takes an argument");
+        };
+        match (self, rest) {
+            (Combinator::I, []) => x.clone(),
+            (Combinator::K, [_]) => x.clone(),
+            (Combinator::S, [y, z]) => app(app(x.clone(), z.clone()), app(y.clone(), z.clone())),
+            (Combinator::B, [y, z]) => app(x.clone(), app(y.clone(), z.clone())),
+            (Combinator::C, [y, z]) => app(app(x.clone(), z.clone()), y.clone()),
+            _ => unreachable!("called with `arity` arguments"),
+        }
+    }
+}
+
+impl fmt::Display for Combinator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// A term of combinatory logic, variables are the free variables of the
+/// lambda term it was translated from.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CombinatorTerm {
+    Combinator(Combinator),
+    Variable(String),
+    Application(Box<CombinatorTerm>, Box<CombinatorTerm>),
+}
+
+fn app(function: CombinatorTerm, argument: CombinatorTerm) -> CombinatorTerm {
+    CombinatorTerm::Application(Box::new(function), Box::new(argument))
+}
+
+impl CombinatorTerm {
+    fn occurs(&self, name: &str) -> bool {
+        match self {
+            CombinatorTerm::Combinator(_) => false,
+            CombinatorTerm::Variable(variable) => variable == name,
+            CombinatorTerm::Application(function, argument) => {
+                function.occurs(name) || argument.occurs(name)
+            }
+        }
+    }
+
+    /// The number of combinators, variables and applications in the term.
+    pub fn size(&self) -> usize {
+        match self {
+            CombinatorTerm::Combinator(_) | CombinatorTerm::Variable(_) => 1,
+            CombinatorTerm::Application(function, argument) => {
+                1 + function.size() + argument.size()
+            }
+        }
+    }
+}
+
+impl fmt::Display for CombinatorTerm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CombinatorTerm::Combinator(combinator) => write!(f, "{}", combinator),
+            CombinatorTerm::Variable(name) => f.write_str(name),
+            CombinatorTerm::Application(function, argument) => match argument.as_ref() {
+                CombinatorTerm::Application(_, _) => write!(f, "{} ({})", function, argument),
+                _ => write!(f, "{} {}", function, argument),
+            },
+        }
+    }
+}
+
+/// The combinators bracket abstraction may use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Basis {
+    Ski,
+    /// also uses `B` and `C` when the variable only occurs on one side of an
+    /// application, and drops it from `f x` when it isn't free in `f`, which
+    /// gives much smaller terms
+    SkiBc,
+}
+
+/// A term without `name` that behaves like `λname.term` when applied.
+fn abstract_variable(name: &str, term: CombinatorTerm, basis: Basis) -> CombinatorTerm {
+    let combinator = CombinatorTerm::Combinator;
+    if !term.occurs(name) {
+        return app(combinator(Combinator::K), term);
+    }
+    match term {
+        CombinatorTerm::Variable(_) => combinator(Combinator::I),
+        CombinatorTerm::Application(function, argument) => {
+            let in_function = function.occurs(name);
+            match (basis, *argument) {
+                (Basis::SkiBc, CombinatorTerm::Variable(variable))
+                    if variable == name && !in_function =>
+                {
+                    *function
+                }
+                (Basis::SkiBc, argument) if !in_function => app(
+                    app(combinator(Combinator::B), *function),
+                    abstract_variable(name, argument, basis),
+                ),
+                (Basis::SkiBc, argument) if !argument.occurs(name) => app(
+                    app(
+                        combinator(Combinator::C),
+                        abstract_variable(name, *function, basis),
+                    ),
+                    argument,
+                ),
+                (_, argument) => app(
+                    app(
+                        combinator(Combinator::S),
+                        abstract_variable(name, *function, basis),
+                    ),
+                    abstract_variable(name, argument, basis),
+                ),
+            }
+        }
+        CombinatorTerm::Combinator(_) => unreachable!("combinators have no variables"),
+    }
+}
+
+/// Translates `expr` to combinatory logic by bracket abstraction, innermost
+/// abstractions first. Free variables are kept.
+pub fn to_combinators(expr: &LambdaExpression, basis: Basis) -> CombinatorTerm {
+    match expr {
+        LambdaExpression::Variable(name) => CombinatorTerm::Variable(name.clone()),
+        LambdaExpression::Abstraction(name, body) => {
+            abstract_variable(name, to_combinators(body, basis), basis)
+        }
+        LambdaExpression::Application(function, argument) => app(
+            to_combinators(function, basis),
+            to_combinators(argument, basis),
+        ),
+    }
+}
+
+/// Replaces every combinator with the lambda term it stands for. Those are
+/// closed, so no free variable of `term` can be captured.
+pub fn from_combinators(term: &CombinatorTerm) -> LambdaExpression {
+    match term {
+        CombinatorTerm::Combinator(combinator) => combinator.to_lambda(),
+        CombinatorTerm::Variable(name) => LambdaExpression::Variable(name.clone()),
+        CombinatorTerm::Application(function, argument) => LambdaExpression::Application(
+            Box::new(from_combinators(function)),
+            Box::new(from_combinators(argument)),
+        ),
+    }
+}
+
+/// Contracts the leftmost outermost redex, `None` when `term` is in normal
+/// form.
+pub fn step(term: &CombinatorTerm) -> Option<CombinatorTerm> {
+    let mut head = term;
+    let mut arguments = vec![];
+    while let CombinatorTerm::Application(function, argument) = head {
+        arguments.push(argument.as_ref().clone());
+        head = function;
+    }
+    arguments.reverse();
+
+    let rebuild = |head: CombinatorTerm, arguments: &[CombinatorTerm]| {
+        arguments.iter().cloned().fold(head, app)
+    };
+    if let CombinatorTerm::Combinator(combinator) = head {
+        let arity = combinator.arity();
+        if arguments.len() >= arity {
+            let contracted = combinator.contract(&arguments[..arity]);
+            return Some(rebuild(contracted, &arguments[arity..]));
+        }
+    }
+    // the head can't be contracted, so the arguments are reduced from left
+    // to right
+    for index in 0..arguments.len() {
+        if let Some(reduced) = step(&arguments[index]) {
+            arguments[index] = reduced;
+            return Some(rebuild(head.clone(), &arguments));
+        }
+    }
+    None
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CombinatorEvaluation {
+    pub term: CombinatorTerm,
+    pub steps: usize,
+    pub outcome: Outcome,
+}
+
+/// Reduces `term` until it is in normal form, it is found to diverge or
+/// `max_steps` reductions have been performed.
+pub fn evaluate(term: &CombinatorTerm, max_steps: usize) -> CombinatorEvaluation {
+    let mut seen = HashSet::from([term.clone()]);
+    let mut term = term.clone();
+    for steps in 0..max_steps {
+        let Some(next) = step(&term) else {
+            return CombinatorEvaluation {
+                term,
+                steps,
+                outcome: Outcome::NormalForm,
+            };
+        };
+        if !seen.insert(next.clone()) {
+            return CombinatorEvaluation {
+                term: next,
+                steps: steps + 1,
+                outcome: Outcome::Diverges,
+            };
+        }
+        term = next;
+    }
+    let outcome = match step(&term) {
+        None => Outcome::NormalForm,
+        Some(_) => Outcome::OutOfSteps,
+    };
+    CombinatorEvaluation {
+        term,
+        steps: max_steps,
+        outcome,
+    }
+}
+
+/// The size of a term in each encoding, see `LambdaExpression::size` and
+/// `CombinatorTerm::size`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sizes {
+    pub lambda: usize,
+    pub ski: usize,
+    pub skibc: usize,
+}
+
+pub fn sizes(expr: &LambdaExpression) -> Sizes {
+    Sizes {
+        lambda: expr.size(),
+        ski: to_combinators(expr, Basis::Ski).size(),
+        skibc: to_combinators(expr, Basis::SkiBc).size(),
+    }
+}
+
+impl fmt::Display for Sizes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "λ: {}, SKI: {}, SKIBC: {}",
+            self.lambda, self.ski, self.skibc
+        )
+    }
+}
+
+/// Radius of every sub term, computed bottom up before anything is placed.
+struct Measured<'a> {
+    term: &'a CombinatorTerm,
+    radius: f64,
+    children: Vec<Measured<'a>>,
+}
+
+fn measure<'a>(term: &'a CombinatorTerm, options: &LayoutOptions) -> Measured<'a> {
+    match term {
+        CombinatorTerm::Combinator(_) | CombinatorTerm::Variable(_) => Measured {
+            term,
+            radius: options.variable_radius * 2.0,
+            children: vec![],
+        },
+        CombinatorTerm::Application(function, argument) => {
+            let function = measure(function, options);
+            let argument = measure(argument, options);
+            Measured {
+                term,
+                radius: function.radius + argument.radius + options.padding,
+                children: vec![function, argument],
+            }
+        }
+    }
+}
+
+fn place(measured: &Measured, center: (f64, f64), options: &LayoutOptions, group: Group) -> Group {
+    let circle = Circle::new()
+        .set("cx", center.0)
+        .set("cy", center.1)
+        .set("r", measured.radius);
+    match measured.term {
+        CombinatorTerm::Combinator(_) | CombinatorTerm::Variable(_) => {
+            // combinators are filled, free variables hollow
+            let (fill, color) = match measured.term {
+                CombinatorTerm::Combinator(_) => ("black", "white"),
+                _ => ("white", "black"),
+            };
+            group.add(circle.set("fill", fill)).add(
+                Text::new(measured.term.to_string())
+                    .set("x", center.0)
+                    .set("y", center.1)
+                    .set("text-anchor", "middle")
+                    .set("dominant-baseline", "central")
+                    .set("font-size", options.variable_radius * 2.5)
+                    .set("fill", color)
+                    .set("stroke", "none"),
+            )
+        }
+        CombinatorTerm::Application(_, _) => {
+            let (function, argument) = (&measured.children[0], &measured.children[1]);
+            let inner = measured.radius - options.padding;
+            let group = group.add(circle);
+            let group = place(
+                function,
+                (center.0 - inner + function.radius, center.1),
+                options,
+                group,
+            );
+            place(
+                argument,
+                (center.0 + inner - argument.radius, center.1),
+                options,
+                group,
+            )
+        }
+    }
+}
+
+/// Draws `term` with a circle around every application, enclosing its
+/// function on the left and its argument on the right. Lambda diagrams use
+/// circles for abstractions instead, combinatory logic has none.
+pub fn to_document(term: &CombinatorTerm, options: &LayoutOptions) -> Document {
+    let measured = measure(term, options);
+    let extent = measured.radius + options.margin;
+    Document::new()
+        .set("viewBox", (-extent, -extent, 2.0 * extent, 2.0 * extent))
+        .add(draw(&measured, options))
+}
+
+/// The drawing of `to_document`, centered on the origin.
+pub fn to_group(term: &CombinatorTerm, options: &LayoutOptions) -> Group {
+    draw(&measure(term, options), options)
+}
+
+fn draw(measured: &Measured, options: &LayoutOptions) -> Group {
+    let group = Group::new()
+        .set("fill", "none")
+        .set("stroke", "black")
+        .set("stroke-width", options.stroke_width);
+    place(measured, (0.0, 0.0), options, group)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de_bruijn::alpha_eq;
+    use crate::evaluator::{self, Strategy};
+
+    const TERMS: [&str; 6] = [
+        "λx.x",
+        "λx.λy.x",
+        "λf.λx.f (f x)",
+        "λn.λf.λx.n(λg.λh.h(g f))(λu.x)(λu.u)",
+        "λx.λy.y x",
+        "λx.f x y",
+    ];
+
+    fn translate(source: &str, basis: Basis) -> String {
+        to_combinators(&parse(source).unwrap(), basis).to_string()
+    }
+
+    #[test]
+    fn test_to_combinators() {
+        assert_eq!(translate("λx.x", Basis::Ski), "I");
+        assert_eq!(translate("λx.λy.x", Basis::Ski), "S (K K) I");
+        assert_eq!(
+            translate("λx.λy.y x", Basis::Ski),
+            "S (K (S I)) (S (K K) I)"
+        );
+        assert_eq!(translate("λx.f x", Basis::Ski), "S (K f) I");
+        assert_eq!(translate("λx.f x", Basis::SkiBc), "f");
+        assert_eq!(translate("λx.λy.y x", Basis::SkiBc), "C I");
+        assert_eq!(translate("λf.λx.f (f x)", Basis::SkiBc), "S B I");
+    }
+
+    #[test]
+    fn test_round_trip_normalizes_to_the_same_term() {
+        for source in TERMS {
+            let expr = parse(source).unwrap();
+            let expected = evaluator::evaluate(&expr, Strategy::NormalOrder, 1000).expr;
+            for basis in [Basis::Ski, Basis::SkiBc] {
+                let back = from_combinators(&to_combinators(&expr, basis));
+                let normal = evaluator::evaluate(&back, Strategy::NormalOrder, 1000).expr;
+                assert!(
+                    alpha_eq(&normal, &expected),
+                    "{} {:?}: {}",
+                    source,
+                    basis,
+                    normal
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        // S K K a = K a (K a) = a
+        let term = app(
+            app(
+                app(
+                    CombinatorTerm::Combinator(Combinator::S),
+                    CombinatorTerm::Combinator(Combinator::K),
+                ),
+                CombinatorTerm::Combinator(Combinator::K),
+            ),
+            CombinatorTerm::Variable("a".to_string()),
+        );
+        let evaluation = evaluate(&term, 100);
+        assert_eq!(evaluation.term, CombinatorTerm::Variable("a".to_string()));
+        assert_eq!(evaluation.steps, 2);
+        assert_eq!(evaluation.outcome, Outcome::NormalForm);
+    }
+
+    #[test]
+    fn test_evaluate_translated_terms() {
+        // applying the translation to arguments gives what the lambda term does
+        let pred_2 = parse("(λn.λf.λx.n(λg.λh.h(g f))(λu.x)(λu.u)) (λf.λx.f (f x)) f x").unwrap();
+        for basis in [Basis::Ski, Basis::SkiBc] {
+            let evaluation = evaluate(&to_combinators(&pred_2, basis), 1000);
+            assert_eq!(evaluation.outcome, Outcome::NormalForm);
+            assert_eq!(evaluation.term.to_string(), "f x");
+        }
+    }
+
+    #[test]
+    fn test_evaluate_omega_runs_out_of_steps() {
+        // `S I I (S I I)` keeps growing rather than cycling back
+        let omega = to_combinators(&parse("(λx.x x)(λx.x x)").unwrap(), Basis::Ski);
+        assert_eq!(omega.to_string(), "S I I (S I I)");
+        let evaluation = evaluate(&omega, 100);
+        assert_eq!(evaluation.outcome, Outcome::OutOfSteps);
+        assert_eq!(evaluation.steps, 100);
+    }
+
+    #[test]
+    fn test_sizes() {
+        let sizes = sizes(&parse("λf.(λx.x x)(λx.f (x x))").unwrap());
+        assert_eq!(sizes.lambda, 12);
+        assert!(sizes.skibc < sizes.ski, "{}", sizes);
+    }
+
+    #[test]
+    fn test_to_document() {
+        let term = to_combinators(&parse("λx.λy.x").unwrap(), Basis::Ski);
+        let document = to_document(&term, &LayoutOptions::default()).to_string();
+        // `S (K K) I` has three applications and four combinators
+        assert_eq!(document.matches("<circle").count(), 7);
+        assert_eq!(document.matches("<text").count(), 4);
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::HashSet;
+
+use crate::ast::LambdaExpression;
+
+/// A lambda term with its bound variables replaced by de Bruijn indices, so
+/// alpha-equivalent terms are represented by the same value.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DeBruijn {
+    /// a bound variable, `0` refers to the innermost enclosing abstraction
+    Index(usize),
+    /// a free variable keeps its name
+    Free(String),
+    Abstraction(Box<DeBruijn>),
+    Application(Box<DeBruijn>, Box<DeBruijn>),
+}
+
+fn convert<'a>(expr: &'a LambdaExpression, scope: &mut Vec<&'a str>) -> DeBruijn {
+    match expr {
+        LambdaExpression::Variable(name) => {
+            match scope.iter().rev().position(|bound| bound == name) {
+                Some(index) => DeBruijn::Index(index),
+                None => DeBruijn::Free(name.clone()),
+            }
+        }
+        LambdaExpression::Abstraction(name, body) => {
+            scope.push(name);
+            let body = convert(body, scope);
+            scope.pop();
+            DeBruijn::Abstraction(Box::new(body))
+        }
+        LambdaExpression::Application(function, argument) => DeBruijn::Application(
+            Box::new(convert(function, scope)),
+            Box::new(convert(argument, scope)),
+        ),
+    }
+}
+
+pub fn to_de_bruijn(expr: &LambdaExpression) -> DeBruijn {
+    convert(expr, &mut vec![])
+}
+
+fn free_names(term: &DeBruijn, free: &mut HashSet<String>) {
+    match term {
+        DeBruijn::Index(_) => (),
+        DeBruijn::Free(name) => {
+            free.insert(name.clone());
+        }
+        DeBruijn::Abstraction(body) => free_names(body, free),
+        DeBruijn::Application(function, argument) => {
+            free_names(function, free);
+            free_names(argument, free);
+        }
+    }
+}
+
+/// Name of the binder of every abstraction nested `depth` deep: `a` to `z`,
+/// then `a1` to `z1` and so on. Type variables are named the same way.
+pub(crate) fn binder_name(depth: usize) -> String {
+    let letter = (b'a' + (depth % 26) as u8) as char;
+    match depth / 26 {
+        0 => letter.to_string(),
+        round => format!("{}{}", letter, round),
+    }
+}
+
+struct Namer {
+    free: HashSet<String>,
+    /// binder name for each depth, skipping names of free variables
+    names: Vec<String>,
+    next: usize,
+}
+
+impl Namer {
+    fn name(&mut self, depth: usize) -> String {
+        while self.names.len() <= depth {
+            let mut name = binder_name(self.next);
+            while self.free.contains(&name) {
+                self.next += 1;
+                name = binder_name(self.next);
+            }
+            self.next += 1;
+            self.names.push(name);
+        }
+        self.names[depth].clone()
+    }
+
+    fn convert(&mut self, term: &DeBruijn, depth: usize) -> LambdaExpression {
+        match term {
+            DeBruijn::Index(index) => LambdaExpression::Variable(self.name(depth - 1 - index)),
+            DeBruijn::Free(name) => LambdaExpression::Variable(name.clone()),
+            DeBruijn::Abstraction(body) => LambdaExpression::Abstraction(
+                self.name(depth),
+                Box::new(self.convert(body, depth + 1)),
+            ),
+            DeBruijn::Application(function, argument) => LambdaExpression::Application(
+                Box::new(self.convert(function, depth)),
+                Box::new(self.convert(argument, depth)),
+            ),
+        }
+    }
+}
+
+/// Names every binder after how deeply it is nested, the names never clash
+/// with an enclosing binder or a free variable so nothing gets captured.
+///
+/// Panics when an index refers past the outermost abstraction.
+pub fn from_de_bruijn(term: &DeBruijn) -> LambdaExpression {
+    let mut free = HashSet::new();
+    free_names(term, &mut free);
+    Namer {
+        free,
+        names: vec![],
+        next: 0,
+    }
+    .convert(term, 0)
+}
+
+/// Tells if `a` and `b` only differ in the names of their bound variables.
+pub fn alpha_eq(a: &LambdaExpression, b: &LambdaExpression) -> bool {
+    to_de_bruijn(a) == to_de_bruijn(b)
+}
+
+/// Renames the bound variables of `expr` so alpha-equivalent terms become
+/// equal: `λx.λy.x` and `λp.λq.p` both become `λa.λb.a`.
+pub fn canonical(expr: &LambdaExpression) -> LambdaExpression {
+    from_de_bruijn(&to_de_bruijn(expr))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn index(index: usize) -> DeBruijn {
+        DeBruijn::Index(index)
+    }
+
+    fn abs(body: DeBruijn) -> DeBruijn {
+        DeBruijn::Abstraction(Box::new(body))
+    }
+
+    fn app(function: DeBruijn, argument: DeBruijn) -> DeBruijn {
+        DeBruijn::Application(Box::new(function), Box::new(argument))
+    }
+
+    #[test]
+    fn test_to_de_bruijn() {
+        // λx.λy.λz.x z (y z)
+        assert_eq!(
+            to_de_bruijn(&parse("λx.λy.λz.(x z)(y z)").unwrap()),
+            abs(abs(abs(app(
+                app(index(2), index(0)),
+                app(index(1), index(0))
+            ))))
+        );
+    }
+
+    #[test]
+    fn test_to_de_bruijn_shadowing_and_free() {
+        assert_eq!(
+            to_de_bruijn(&parse("λx.λx.x f").unwrap()),
+            abs(abs(app(index(0), DeBruijn::Free("f".to_string()))))
+        );
+    }
+
+    #[test]
+    fn test_alpha_eq() {
+        assert!(alpha_eq(&parse("λx.x").unwrap(), &parse("λy.y").unwrap()));
+        assert!(alpha_eq(
+            &parse("λf.(λx.x x)(λx.f (x x))").unwrap(),
+            &parse("λg.(λa.a a)(λb.g (b b))").unwrap()
+        ));
+        assert!(!alpha_eq(
+            &parse("λx.λy.x").unwrap(),
+            &parse("λx.λy.y").unwrap()
+        ));
+        assert!(!alpha_eq(&parse("λx.y").unwrap(), &parse("λx.z").unwrap()));
+    }
+
+    #[test]
+    fn test_canonical() {
+        assert_eq!(
+            canonical(&parse("λx.λy.x").unwrap()),
+            canonical(&parse("λp.λq.p").unwrap())
+        );
+        assert_eq!(
+            canonical(&parse("λp.λq.p").unwrap()),
+            parse("λa.λb.a").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_canonical_avoids_free_variables() {
+        assert_eq!(
+            canonical(&parse("λx.λy.a x y").unwrap()),
+            parse("λb.λc.a b c").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for source in [
+            "λn.λf.λx.n(λg.λh.h(g f))(λu.x)(λu.u)",
+            "(λx.x x)(λx.x x)",
+            "λx.λx.x",
+            "f (λy.y z)",
+        ] {
+            let expr = parse(source).unwrap();
+            let term = to_de_bruijn(&expr);
+            assert_eq!(to_de_bruijn(&from_de_bruijn(&term)), term);
+            assert!(alpha_eq(&from_de_bruijn(&term), &expr));
+        }
+    }
+
+    #[test]
+    fn test_binder_names() {
+        assert_eq!(binder_name(0), "a");
+        assert_eq!(binder_name(25), "z");
+        assert_eq!(binder_name(26), "a1");
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use nom::{bytes::complete::tag, sequence::preceded, IResult, Offset};
+
+use crate::ast::LambdaExpression;
+use crate::evaluator::{free_variables, substitute};
+use crate::parser::{
+    expect, is_identifier_continue, parse, parse_lambda_expression, parse_string_variable,
+    unexpected, whitespace,
+};
+use crate::parse_error::{LambdaParseError, ParseError};
+
+/// Church booleans, numerals, pairs, lists and the usual combinators.
+pub const PRELUDE: &str = include_str!("../resources/prelude.lambda");
+
+/// Named terms, a free variable with the name of a definition stands for its
+/// term.
+///
+/// Definitions are written `let name = term;`, a `.lambda` file is a sequence
+/// of them optionally followed by the term to work with:
+///
+/// ```text
+/// let true = λt.λf.t;
+/// let not = λp.p false true;
+/// not true
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Definitions {
+    terms: HashMap<String, LambdaExpression>,
+}
+
+/// Tells if `input` starts with the `let` keyword rather than a name like
+/// `letter`.
+fn is_definition(input: &str) -> bool {
+    input
+        .strip_prefix("let")
+        .is_some_and(|rest| !rest.starts_with(is_identifier_continue))
+}
+
+/// `let name = term;`
+fn parse_definition(
+    input: &str,
+) -> IResult<&str, (&str, LambdaExpression), LambdaParseError<&str>> {
+    let (input, _) = tag("let")(input)?;
+    let (input, name) = expect(
+        || "name after `let`".to_string(),
+        preceded(whitespace, parse_string_variable),
+    )(input)?;
+    let (input, _) = expect(
+        || format!("`=` after `let {}`", name),
+        preceded(whitespace, tag("=")),
+    )(input)?;
+    let (input, expr) = parse_lambda_expression(input).map_err(|err| match err {
+        nom::Err::Error(err) => {
+            nom::Err::Error(err.or_expected(|| "expression after `=`".to_string()))
+        }
+        err => err,
+    })?;
+    let (input, _) = expect(
+        || format!("`;` after the definition of `{}`", name),
+        preceded(whitespace, tag(";")),
+    )(input)?;
+    Ok((input, (name, expr)))
+}
+
+impl Definitions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The definitions of [`PRELUDE`].
+    pub fn prelude() -> Self {
+        let mut definitions = Self::new();
+        definitions
+            .load(PRELUDE)
+            .unwrap_or_else(|err| panic!("the prelude is invalid\n{}", err.render(PRELUDE)));
+        definitions
+    }
+
+    pub fn get(&self, name: &str) -> Option<&LambdaExpression> {
+        self.terms.get(name)
+    }
+
+    /// Every definition, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &LambdaExpression)> {
+        self.terms.iter().map(|(name, expr)| (name.as_str(), expr))
+    }
+
+    /// Defines `name` as `expr`, names in `expr` are expanded right away so a
+    /// definition can only refer to earlier ones. A later definition with the
+    /// same name replaces the earlier one.
+    pub fn define(&mut self, name: impl Into<String>, expr: &LambdaExpression) {
+        let expr = self.expand(expr);
+        self.terms.insert(name.into(), expr);
+    }
+
+    /// Replaces every free variable that names a definition with its term.
+    ///
+    /// The names are replaced all at once, a term that has the name of a
+    /// later definition free keeps it free.
+    pub fn expand(&self, expr: &LambdaExpression) -> LambdaExpression {
+        let mut names: Vec<_> = free_variables(expr)
+            .into_iter()
+            .filter(|name| self.terms.contains_key(name))
+            .collect();
+        names.sort();
+        // `expr` is bound by an abstraction for every name and applied to the
+        // terms one by one, binders for the names still to come are renamed
+        // where they would capture a free variable of a term
+        let bound = names.iter().rev().fold(expr.clone(), |body, name| {
+            LambdaExpression::Abstraction(name.clone(), Box::new(body))
+        });
+        names.iter().fold(bound, |bound, name| {
+            let LambdaExpression::Abstraction(binder, body) = bound else {
+                unreachable!("there is an abstraction for every name left");
+            };
+            substitute(&body, &binder, &self.terms[name])
+        })
+    }
+
+    /// Parses `source` and expands the definitions in it.
+    pub fn parse(&self, source: &str) -> Result<LambdaExpression, ParseError> {
+        parse(source).map(|expr| self.expand(&expr))
+    }
+
+    /// Adds the definitions in `source`, returns the term that follows them
+    /// with its names expanded if there is one.
+    pub fn load(&mut self, source: &str) -> Result<Option<LambdaExpression>, ParseError> {
+        Ok(self.load_unexpanded(source)?.map(|expr| self.expand(&expr)))
+    }
+
+    /// Like `load`, with the names of the term left free, for
+    /// `TypeEnvironment::from_definitions`.
+    pub fn load_unexpanded(
+        &mut self,
+        source: &str,
+    ) -> Result<Option<LambdaExpression>, ParseError> {
+        let error = |err: LambdaParseError<&str>| {
+            let rest = err.input.trim_start();
+            let message = err.message.unwrap_or_else(|| unexpected(rest));
+            ParseError::new(source, source.offset(rest), message)
+        };
+
+        let skip = |input| match whitespace(input) {
+            Ok((rest, _)) => Ok(rest),
+            Err(nom::Err::Error(err) | nom::Err::Failure(err)) => Err(error(err)),
+            Err(nom::Err::Incomplete(_)) => unreachable!("only complete parsers are used"),
+        };
+
+        let mut input = skip(source)?;
+        while !input.is_empty() {
+            // anything but a definition has to be the final term
+            if !is_definition(input) {
+                let start = source.offset(input);
+                return parse(input)
+                    .map(Some)
+                    .map_err(|err| ParseError::new(source, start + err.offset, err.message));
+            }
+            let (rest, (name, expr)) = parse_definition(input).map_err(|err| match err {
+                nom::Err::Error(err) | nom::Err::Failure(err) => error(err),
+                nom::Err::Incomplete(_) => unreachable!("only complete parsers are used"),
+            })?;
+            self.define(name, &expr);
+            input = skip(rest)?;
+        }
+        Ok(None)
+    }
+
+    /// Like `load`, with the definitions read from a file.
+    pub fn load_file(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<Option<LambdaExpression>, Box<dyn std::error::Error>> {
+        let source = std::fs::read_to_string(path)?;
+        Ok(self.load(&source).map_err(|err| err.render(&source))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de_bruijn::alpha_eq;
+    use crate::evaluator::{evaluate, Outcome, Strategy};
+
+    fn evaluate_with_prelude(source: &str) -> LambdaExpression {
+        let prelude = Definitions::prelude();
+        let evaluation = evaluate(&prelude.parse(source).unwrap(), Strategy::NormalOrder, 1000);
+        assert_eq!(evaluation.outcome, Outcome::NormalForm);
+        evaluation.expr
+    }
+
+    macro_rules! test_prelude {
+        ($name:ident, $expr:expr, $expected:expr) => {
+            ::paste::paste! {
+                #[test]
+                fn [<test_prelude_$name>]() {
+                    let result = evaluate_with_prelude($expr);
+                    let expected = Definitions::prelude().parse($expected).unwrap();
+                    assert!(alpha_eq(&result, &expected), "{} is not {}", result, $expected);
+                }
+            }
+        };
+    }
+
+    test_prelude!(identity, "I a", "a");
+    test_prelude!(skk, "S K K a", "a");
+    test_prelude!(not, "not true", "false");
+    test_prelude!(and, "and true false", "false");
+    test_prelude!(or, "or false true", "true");
+    test_prelude!(pred, "PRED two", "one");
+    test_prelude!(plus, "plus two two", "four");
+    test_prelude!(mult, "mult two two", "four");
+    test_prelude!(sub, "sub four one", "three");
+    test_prelude!(literals, "plus 2 (mult 3 4)", "14");
+    test_prelude!(iszero, "iszero (PRED one)", "true");
+    test_prelude!(snd, "snd (pair a b)", "b");
+    test_prelude!(list, "head (tail (cons a (cons b nil)))", "b");
+    test_prelude!(isnil, "and (isnil nil) (not (isnil (cons a nil)))", "true");
+
+    #[test]
+    fn test_prelude_is_closed() {
+        let prelude = Definitions::prelude();
+        for (name, expr) in &prelude.terms {
+            assert!(free_variables(expr).is_empty(), "`{}` is not closed", name);
+        }
+        assert_eq!(
+            prelude.get("Y"),
+            Some(&parse("λf.(λx.x x)(λx.f (x x))").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_load() {
+        let mut definitions = Definitions::new();
+        let expr = definitions.load("let id = λx.x;\nlet twice = λf.λx.f (f x);\ntwice id y");
+        assert_eq!(expr, Ok(Some(parse("(λf.λx.f (f x)) (λx.x) y").unwrap())));
+        assert_eq!(definitions.load("let k = λx.λy.x;\n"), Ok(None));
+        assert_eq!(definitions.get("k"), Some(&parse("λx.λy.x").unwrap()));
+    }
+
+    #[test]
+    fn test_load_unexpanded() {
+        let mut definitions = Definitions::new();
+        let expr = definitions.load_unexpanded("let id = λx.x;\nid y");
+        assert_eq!(expr, Ok(Some(parse("id y").unwrap())));
+        assert_eq!(definitions.get("id"), Some(&parse("λx.x").unwrap()));
+    }
+
+    #[test]
+    fn test_definitions_refer_to_earlier_ones() {
+        let mut definitions = Definitions::new();
+        definitions
+            .load("let id = λx.x; let both = id id;")
+            .unwrap();
+        assert_eq!(
+            definitions.get("both"),
+            Some(&parse("(λx.x) (λx.x)").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_load_with_comments() {
+        let mut definitions = Definitions::new();
+        let source =
+            "-- combinators\nlet id = {- identity -} λx.x;\n\n{- the\n   term -}\nid -- end";
+        assert_eq!(definitions.load(source), Ok(Some(parse("λx.x").unwrap())));
+    }
+
+    #[test]
+    fn test_name_starting_with_let_is_a_term() {
+        let mut definitions = Definitions::new();
+        assert_eq!(
+            definitions.load("letter"),
+            Ok(Some(parse("letter").unwrap()))
+        );
+    }
+
+    #[test]
+    fn test_expand_avoids_capture() {
+        let mut definitions = Definitions::new();
+        definitions.define("f", &parse("λx.y").unwrap());
+        assert_eq!(
+            definitions.expand(&parse("λy.f").unwrap()),
+            parse("λy'.λx.y").unwrap()
+        );
+        // bound variables and undefined names are left alone
+        assert_eq!(
+            definitions.expand(&parse("λf.f g").unwrap()),
+            parse("λf.f g").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_expand_keeps_later_names_free() {
+        let mut definitions = Definitions::new();
+        let expr = definitions
+            .load("let a = λx.b; let b = λy.y; a b")
+            .unwrap()
+            .unwrap();
+        assert_eq!(expr, parse("(λx.b) (λy.y)").unwrap());
+        let evaluation = evaluate(&expr, Strategy::NormalOrder, 10);
+        assert_eq!(evaluation.expr, parse("b").unwrap());
+    }
+
+    macro_rules! test_load_error {
+        ($name:ident, $source:expr, $line:expr, $column:expr, $message:expr) => {
+            ::paste::paste! {
+                #[test]
+                fn [<test_load_error_$name>]() {
+                    let error = Definitions::new().load($source).unwrap_err();
+                    assert_eq!(
+                        (error.line, error.column, error.message.as_str()),
+                        ($line, $column, $message)
+                    );
+                }
+            }
+        };
+    }
+
+    test_load_error!(missing_name, "let = x;", 1, 5, "expected name after `let`");
+    test_load_error!(
+        missing_equals,
+        "let a x;",
+        1,
+        7,
+        "expected `=` after `let a`"
+    );
+    test_load_error!(
+        missing_term,
+        "let a = ;",
+        1,
+        9,
+        "expected expression after `=`"
+    );
+    test_load_error!(
+        missing_semicolon,
+        "let a = x\nlet b = y;",
+        2,
+        7,
+        "expected `;` after the definition of `a`"
+    );
+    test_load_error!(in_definition, "let a = (x = y);", 1, 12, "unexpected `=`");
+    test_load_error!(
+        unterminated_comment,
+        "let a = x;\n{- a",
+        2,
+        1,
+        "unterminated block comment"
+    );
+    test_load_error!(in_term, "let a = x;\n\nf (a", 3, 3, "unbalanced `(`");
+}
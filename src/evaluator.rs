@@ -0,0 +1,528 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::de_bruijn::to_de_bruijn;
+use crate::ast::LambdaExpression;
+
+/// Order in which redexes are contracted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// leftmost outermost redex first, finds the normal form if there is one
+    NormalOrder,
+    /// leftmost innermost redex first, arguments are normalized before they
+    /// are substituted
+    ApplicativeOrder,
+    /// leftmost outermost, but never under an abstraction or in an argument
+    CallByName,
+    /// arguments are evaluated before they are substituted, but never under an
+    /// abstraction
+    CallByValue,
+    /// only the head redex is contracted, stops at a head normal form
+    HeadReduction,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// no redex is left that the strategy would contract
+    NormalForm,
+    /// the term reduced back to a term it was before, so it never terminates
+    Diverges,
+    /// the step budget ran out, the term may or may not have a normal form
+    OutOfSteps,
+    /// the next term would be nested deeper than `MAX_DEPTH`, the term is the
+    /// last one that wasn't
+    TooDeep,
+}
+
+/// Terms nested deeper than this, see `LambdaExpression::depth`, would
+/// overflow the stack of the recursive functions reducing them.
+pub const MAX_DEPTH: usize = 1_500;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Evaluation {
+    pub expr: LambdaExpression,
+    pub steps: usize,
+    pub outcome: Outcome,
+}
+
+/// The variables `expr` doesn't bind itself. The term is walked with an
+/// explicit stack, so this works on terms of any depth.
+pub fn free_variables(expr: &LambdaExpression) -> HashSet<String> {
+    let mut free = HashSet::new();
+    // the binders around the sub expression being looked at, every sub
+    // expression on the stack is inside the first `n` of them
+    let mut scope: Vec<&str> = vec![];
+    let mut open = vec![(expr, 0)];
+    while let Some((expr, n)) = open.pop() {
+        scope.truncate(n);
+        match expr {
+            LambdaExpression::Variable(name) => {
+                if !scope.contains(&name.as_str()) {
+                    free.insert(name.clone());
+                }
+            }
+            LambdaExpression::Abstraction(name, body) => {
+                scope.push(name);
+                open.push((body, n + 1));
+            }
+            LambdaExpression::Application(function, argument) => {
+                open.push((argument, n));
+                open.push((function, n));
+            }
+        }
+    }
+    free
+}
+
+/// Appends primes to `name` until it is not in `used`.
+fn fresh_name(name: &str, used: &HashSet<String>) -> String {
+    let mut fresh = format!("{}'", name);
+    while used.contains(&fresh) {
+        fresh.push('\'');
+    }
+    fresh
+}
+
+/// Replaces the free occurrences of `name` in `expr` by `value`, binders that
+/// would capture a free variable of `value` are renamed.
+pub fn substitute(
+    expr: &LambdaExpression,
+    name: &str,
+    value: &LambdaExpression,
+) -> LambdaExpression {
+    match expr {
+        LambdaExpression::Variable(variable) if variable == name => value.clone(),
+        LambdaExpression::Variable(_) => expr.clone(),
+        LambdaExpression::Application(function, argument) => LambdaExpression::Application(
+            Box::new(substitute(function, name, value)),
+            Box::new(substitute(argument, name, value)),
+        ),
+        LambdaExpression::Abstraction(binder, _) if binder == name => expr.clone(),
+        LambdaExpression::Abstraction(binder, body) => {
+            let free_in_value = free_variables(value);
+            if !free_in_value.contains(binder) || !free_variables(body).contains(name) {
+                return LambdaExpression::Abstraction(
+                    binder.clone(),
+                    Box::new(substitute(body, name, value)),
+                );
+            }
+            let mut used = free_in_value;
+            used.extend(free_variables(body));
+            used.insert(name.to_string());
+            let renamed = fresh_name(binder, &used);
+            let body = substitute(body, binder, &LambdaExpression::Variable(renamed.clone()));
+            LambdaExpression::Abstraction(renamed, Box::new(substitute(&body, name, value)))
+        }
+    }
+}
+
+/// Which sub expression a `Redex::path` descends into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Child {
+    Body,
+    Function,
+    Argument,
+}
+
+/// The redex `(λbinder.body) argument` contracted by a reduction step.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Redex {
+    /// the way from the root of the term down to the redex
+    pub path: Vec<Child>,
+    pub binder: String,
+    pub argument: LambdaExpression,
+}
+
+impl fmt::Display for Redex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{} := {}]", self.binder, self.argument)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReductionStep {
+    pub redex: Redex,
+    /// the term after the redex was contracted
+    pub expr: LambdaExpression,
+}
+
+/// Every step taken while evaluating `initial`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trace {
+    pub initial: LambdaExpression,
+    pub steps: Vec<ReductionStep>,
+    pub outcome: Outcome,
+}
+
+// while reducing, the path of the redex is built up from the redex to the
+// root, `step_redex` reverses it once the whole way is known
+
+/// Contracts `(λx.body) argument` into `body[x := argument]`.
+fn contract(
+    function: &LambdaExpression,
+    argument: &LambdaExpression,
+) -> Option<(LambdaExpression, Redex)> {
+    match function {
+        LambdaExpression::Abstraction(name, body) => Some((
+            substitute(body, name, argument),
+            Redex {
+                path: vec![],
+                binder: name.clone(),
+                argument: argument.clone(),
+            },
+        )),
+        _ => None,
+    }
+}
+
+fn reduce_function(
+    function: &LambdaExpression,
+    argument: &LambdaExpression,
+    strategy: Strategy,
+) -> Option<(LambdaExpression, Redex)> {
+    reduce(function, strategy).map(|(function, mut redex)| {
+        redex.path.push(Child::Function);
+        (
+            LambdaExpression::Application(Box::new(function), Box::new(argument.clone())),
+            redex,
+        )
+    })
+}
+
+fn reduce_argument(
+    function: &LambdaExpression,
+    argument: &LambdaExpression,
+    strategy: Strategy,
+) -> Option<(LambdaExpression, Redex)> {
+    reduce(argument, strategy).map(|(argument, mut redex)| {
+        redex.path.push(Child::Argument);
+        (
+            LambdaExpression::Application(Box::new(function.clone()), Box::new(argument)),
+            redex,
+        )
+    })
+}
+
+fn reduce(expr: &LambdaExpression, strategy: Strategy) -> Option<(LambdaExpression, Redex)> {
+    match (expr, strategy) {
+        (LambdaExpression::Variable(_), _) => None,
+        (LambdaExpression::Abstraction(_, _), Strategy::CallByName | Strategy::CallByValue) => None,
+        (LambdaExpression::Abstraction(name, body), _) => {
+            reduce(body, strategy).map(|(body, mut redex)| {
+                redex.path.push(Child::Body);
+                (
+                    LambdaExpression::Abstraction(name.clone(), Box::new(body)),
+                    redex,
+                )
+            })
+        }
+        (LambdaExpression::Application(function, argument), Strategy::NormalOrder) => {
+            contract(function, argument)
+                .or_else(|| reduce_function(function, argument, strategy))
+                .or_else(|| reduce_argument(function, argument, strategy))
+        }
+        (
+            LambdaExpression::Application(function, argument),
+            Strategy::CallByName | Strategy::HeadReduction,
+        ) => contract(function, argument).or_else(|| reduce_function(function, argument, strategy)),
+        (
+            LambdaExpression::Application(function, argument),
+            Strategy::ApplicativeOrder | Strategy::CallByValue,
+        ) => reduce_function(function, argument, strategy)
+            .or_else(|| reduce_argument(function, argument, strategy))
+            .or_else(|| contract(function, argument)),
+    }
+}
+
+/// Performs a single beta reduction and tells which redex was contracted,
+/// `None` when `expr` is in normal form for `strategy`.
+pub fn step_redex(
+    expr: &LambdaExpression,
+    strategy: Strategy,
+) -> Option<(LambdaExpression, Redex)> {
+    reduce(expr, strategy).map(|(expr, mut redex)| {
+        redex.path.reverse();
+        (expr, redex)
+    })
+}
+
+/// Performs a single beta reduction, `None` when `expr` is in normal form for
+/// `strategy`.
+pub fn step(expr: &LambdaExpression, strategy: Strategy) -> Option<LambdaExpression> {
+    reduce(expr, strategy).map(|(expr, _)| expr)
+}
+
+fn run(
+    expr: &LambdaExpression,
+    strategy: Strategy,
+    max_steps: usize,
+    mut on_step: impl FnMut(Redex, &LambdaExpression),
+) -> Evaluation {
+    // terms that only differ in the names of bound variables behave the same,
+    // so revisiting one up to renaming means the reduction loops
+    if expr.depth() > MAX_DEPTH {
+        return Evaluation {
+            expr: expr.clone(),
+            steps: 0,
+            outcome: Outcome::TooDeep,
+        };
+    }
+    let mut seen = HashSet::from([to_de_bruijn(expr)]);
+    let mut expr = expr.clone();
+    for steps in 0..max_steps {
+        let Some((next, redex)) = step_redex(&expr, strategy) else {
+            return Evaluation {
+                expr,
+                steps,
+                outcome: Outcome::NormalForm,
+            };
+        };
+        // a single substitution nests the argument at most as deep as the
+        // term already is, so that much is still safe to measure and drop
+        if next.depth() > MAX_DEPTH {
+            return Evaluation {
+                expr,
+                steps,
+                outcome: Outcome::TooDeep,
+            };
+        }
+        on_step(redex, &next);
+        if !seen.insert(to_de_bruijn(&next)) {
+            return Evaluation {
+                expr: next,
+                steps: steps + 1,
+                outcome: Outcome::Diverges,
+            };
+        }
+        expr = next;
+    }
+
+    let outcome = match step(&expr, strategy) {
+        None => Outcome::NormalForm,
+        Some(_) => Outcome::OutOfSteps,
+    };
+    Evaluation {
+        expr,
+        steps: max_steps,
+        outcome,
+    }
+}
+
+/// Reduces `expr` with `strategy` until it is in normal form, it is found to
+/// diverge, it grows too deep or `max_steps` reductions have been performed.
+pub fn evaluate(expr: &LambdaExpression, strategy: Strategy, max_steps: usize) -> Evaluation {
+    run(expr, strategy, max_steps, |_, _| {})
+}
+
+/// Like `evaluate`, but keeps every intermediate term and the redex that was
+/// contracted to get there.
+pub fn trace(expr: &LambdaExpression, strategy: Strategy, max_steps: usize) -> Trace {
+    let mut steps = vec![];
+    let evaluation = run(expr, strategy, max_steps, |redex, expr| {
+        steps.push(ReductionStep {
+            redex,
+            expr: expr.clone(),
+        })
+    });
+    Trace {
+        initial: expr.clone(),
+        steps,
+        outcome: evaluation.outcome,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de_bruijn::alpha_eq;
+    use crate::parser::parse;
+
+    const Y: &str = "λf.(λx.x x)(λx.f (x x))";
+    const PRED: &str = "λn.λf.λx.n(λg.λh.h(g f))(λu.x)(λu.u)";
+    const OMEGA: &str = "(λx.x x)(λx.x x)";
+
+    fn evaluate_source(source: &str, strategy: Strategy) -> Evaluation {
+        evaluate(&parse(source).unwrap(), strategy, 1000)
+    }
+
+    macro_rules! test_evaluate {
+        ($name:ident, $strategy:ident, $expr:expr, $expected:expr, $outcome:ident) => {
+            ::paste::paste! {
+                #[test]
+                fn [<test_evaluate_$name>]() {
+                    let evaluation = evaluate_source(&$expr, Strategy::$strategy);
+                    assert_eq!(evaluation.outcome, Outcome::$outcome);
+                    let expected = parse(&$expected).unwrap();
+                    assert!(
+                        alpha_eq(&evaluation.expr, &expected),
+                        "{} is not alpha-equivalent to {}",
+                        evaluation.expr,
+                        expected
+                    );
+                }
+            }
+        };
+    }
+
+    test_evaluate!(identity, NormalOrder, "(λx.x) y", "y", NormalForm);
+    test_evaluate!(k, NormalOrder, "(λx.λy.x) a b", "a", NormalForm);
+    test_evaluate!(
+        pred_2,
+        NormalOrder,
+        format!("({}) (λf.λx.f (f x))", PRED),
+        "λf.λx.f x",
+        NormalForm
+    );
+    test_evaluate!(
+        pred_2_applicative,
+        ApplicativeOrder,
+        format!("({}) (λf.λx.f (f x))", PRED),
+        "λf.λx.f x",
+        NormalForm
+    );
+    test_evaluate!(omega, NormalOrder, OMEGA, OMEGA, Diverges);
+    test_evaluate!(
+        discarded_omega_normal_order,
+        NormalOrder,
+        format!("(λx.z) ({})", OMEGA),
+        "z",
+        NormalForm
+    );
+    test_evaluate!(
+        discarded_omega_call_by_name,
+        CallByName,
+        format!("(λx.z) ({})", OMEGA),
+        "z",
+        NormalForm
+    );
+    test_evaluate!(
+        discarded_omega_applicative_order,
+        ApplicativeOrder,
+        format!("(λx.z) ({})", OMEGA),
+        format!("(λx.z) ({})", OMEGA),
+        Diverges
+    );
+    test_evaluate!(
+        discarded_omega_call_by_value,
+        CallByValue,
+        format!("(λx.z) ({})", OMEGA),
+        format!("(λx.z) ({})", OMEGA),
+        Diverges
+    );
+    test_evaluate!(
+        call_by_name_stops_at_abstraction,
+        CallByName,
+        "(λx.x) λy.(λz.z) y",
+        "λy.(λz.z) y",
+        NormalForm
+    );
+    test_evaluate!(
+        call_by_value_stops_at_abstraction,
+        CallByValue,
+        "(λx.x) λy.(λz.z) y",
+        "λy.(λz.z) y",
+        NormalForm
+    );
+    test_evaluate!(
+        head_reduction_leaves_arguments,
+        HeadReduction,
+        "λx.(λy.y) x ((λz.z) x)",
+        "λx.x ((λz.z) x)",
+        NormalForm
+    );
+    test_evaluate!(y_call_by_name, CallByName, Y, Y, NormalForm);
+    test_evaluate!(
+        y_head_reduction,
+        HeadReduction,
+        Y,
+        "λf.f ((λx.f (x x)) (λx.f (x x)))",
+        NormalForm
+    );
+
+    #[test]
+    fn test_evaluate_y_normal_order_runs_out_of_steps() {
+        let evaluation = evaluate(&parse(Y).unwrap(), Strategy::NormalOrder, 50);
+        assert_eq!(evaluation.outcome, Outcome::OutOfSteps);
+        assert_eq!(evaluation.steps, 50);
+    }
+
+    #[test]
+    fn test_evaluate_detects_loops_up_to_renaming() {
+        let evaluation = evaluate_source("(λx.x x)(λy.y y)", Strategy::NormalOrder);
+        assert_eq!(evaluation.outcome, Outcome::Diverges);
+        assert_eq!(evaluation.steps, 1);
+    }
+
+    #[test]
+    fn test_evaluate_stops_before_terms_get_too_deep() {
+        // each step nests a copy of a numeral into the other one
+        let expr = parse("λx. 1000 (1000 x)").unwrap();
+        let evaluation = evaluate(&expr, Strategy::NormalOrder, 10_000);
+        assert_eq!(evaluation.outcome, Outcome::TooDeep);
+        assert!(evaluation.expr.depth() <= MAX_DEPTH);
+        // terms that are too deep to begin with aren't reduced at all
+        let too_deep = (0..MAX_DEPTH).fold(parse("(λx.x) y").unwrap(), |body, _| {
+            LambdaExpression::Abstraction("z".to_string(), Box::new(body))
+        });
+        let evaluation = evaluate(&too_deep, Strategy::NormalOrder, 10);
+        assert_eq!((evaluation.outcome, evaluation.steps), (Outcome::TooDeep, 0));
+    }
+
+    #[test]
+    fn test_evaluate_counts_steps() {
+        let evaluation = evaluate_source("(λx.λy.x) a b", Strategy::NormalOrder);
+        assert_eq!(evaluation.steps, 2);
+    }
+
+    #[test]
+    fn test_substitute_avoids_capture() {
+        let expr = parse("λy.x y").unwrap();
+        let substituted = substitute(&expr, "x", &parse("y").unwrap());
+        assert_eq!(substituted, parse("λy'.y y'").unwrap());
+    }
+
+    #[test]
+    fn test_substitute_avoids_capture_of_renamed_binder() {
+        let expr = parse("λy.x y y'").unwrap();
+        let substituted = substitute(&expr, "x", &parse("y").unwrap());
+        assert_eq!(substituted, parse("λy''.y y'' y'").unwrap());
+    }
+
+    #[test]
+    fn test_substitute_respects_shadowing() {
+        let expr = parse("λx.x").unwrap();
+        assert_eq!(substitute(&expr, "x", &parse("y").unwrap()), expr);
+    }
+
+    #[test]
+    fn test_free_variables() {
+        let expr = parse("λx.f x (λy.y z)").unwrap();
+        assert_eq!(
+            free_variables(&expr),
+            HashSet::from(["f".to_string(), "z".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_step_redex_path() {
+        let expr = parse("λf.f ((λx.x) f)").unwrap();
+        let (reduced, redex) = step_redex(&expr, Strategy::NormalOrder).unwrap();
+        assert_eq!(reduced, parse("λf.f f").unwrap());
+        assert_eq!(redex.path, vec![Child::Body, Child::Argument]);
+        assert_eq!(redex.binder, "x");
+        assert_eq!(redex.argument, parse("f").unwrap());
+        assert_eq!(redex.to_string(), "[x := f]");
+    }
+
+    #[test]
+    fn test_trace() {
+        let trace = trace(&parse("(λx.λy.x) a b").unwrap(), Strategy::NormalOrder, 10);
+        assert_eq!(trace.outcome, Outcome::NormalForm);
+        assert_eq!(
+            trace.steps.iter().map(|step| step.expr.to_string()).collect::<Vec<_>>(),
+            vec!["(λy.a) b", "a"]
+        );
+        assert_eq!(trace.steps[0].redex.path, vec![Child::Function]);
+        assert_eq!(trace.steps[1].redex.path, vec![]);
+    }
+}
@@ -0,0 +1,326 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use resvg::usvg::fontdb::{self, Family, Query, Source, ID};
+use svg::node::element::Style;
+
+/// The family of the bundled Tengwar Annatar fonts.
+pub const TENGWAR_ANNATAR: &str = "Tengwar Annatar";
+
+const BUNDLED: [&[u8]; 2] = [
+    include_bytes!("../resources/fonts/TengwarAnnatarBoldItalic-K7r7.ttf"),
+    include_bytes!("../resources/fonts/TengwarAnnatarAltBoldItalic-YzDo.ttf"),
+];
+
+/// A font to load, the first one given to a `FontConfig` is the one text is
+/// written in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FontSource {
+    /// a TrueType or OpenType file
+    Path(PathBuf),
+    /// a family that is already loaded, from the system or the bundled fonts
+    Family(String),
+}
+
+impl FontSource {
+    /// An existing file or a name with the extension of a font file is taken
+    /// as a path, anything else as a family.
+    pub fn guess(value: &str) -> Self {
+        let path = Path::new(value);
+        let font_extension = path.extension().is_some_and(|extension| {
+            ["ttf", "otf", "ttc", "otc"]
+                .iter()
+                .any(|font| extension.eq_ignore_ascii_case(font))
+        });
+        if path.is_file() || font_extension {
+            FontSource::Path(value.into())
+        } else {
+            FontSource::Family(value.to_string())
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum FontError {
+    /// the font file couldn't be read
+    Unreadable { path: PathBuf, error: io::Error },
+    /// the file was read but has no font in it
+    NotAFont(PathBuf),
+    /// no loaded font has this family
+    UnknownFamily(String),
+    /// the font couldn't be cut down to the glyphs that are used
+    Subset {
+        family: String,
+        error: subsetter::Error,
+    },
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FontError::Unreadable { path, error } => {
+                write!(f, "can't read font `{}`: {}", path.display(), error)
+            }
+            FontError::NotAFont(path) => write!(f, "`{}` is not a font", path.display()),
+            FontError::UnknownFamily(family) => write!(f, "no font named `{}` is loaded", family),
+            FontError::Subset { family, error } => {
+                write!(f, "can't subset font `{}`: {:?}", family, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FontError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FontError::Unreadable { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Which fonts to load, built up step by step and loaded with `load`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FontConfig {
+    sources: Vec<FontSource>,
+    system_fonts: bool,
+    bundled_fonts: bool,
+}
+
+impl Default for FontConfig {
+    fn default() -> Self {
+        FontConfig {
+            sources: vec![],
+            system_fonts: true,
+            bundled_fonts: true,
+        }
+    }
+}
+
+impl FontConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn font(mut self, source: FontSource) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    pub fn system_fonts(mut self, enabled: bool) -> Self {
+        self.system_fonts = enabled;
+        self
+    }
+
+    /// The Tengwar Annatar fonts built into the crate, so they are found
+    /// wherever it runs from.
+    pub fn bundled_fonts(mut self, enabled: bool) -> Self {
+        self.bundled_fonts = enabled;
+        self
+    }
+
+    pub fn load(&self) -> Result<Fonts, FontError> {
+        let mut database = fontdb::Database::new();
+        if self.system_fonts {
+            database.load_system_fonts();
+        }
+        if self.bundled_fonts {
+            for data in BUNDLED {
+                database.load_font_data(data.to_vec());
+            }
+        }
+
+        let mut families = vec![];
+        for source in &self.sources {
+            match source {
+                FontSource::Path(path) => {
+                    let data = std::fs::read(path).map_err(|error| FontError::Unreadable {
+                        path: path.clone(),
+                        error,
+                    })?;
+                    let ids = database.load_font_source(Source::Binary(Arc::new(data)));
+                    let family = ids
+                        .first()
+                        .and_then(|&id| database.face(id))
+                        .and_then(|face| face.families.first())
+                        .map(|(family, _)| family.clone())
+                        .ok_or_else(|| FontError::NotAFont(path.clone()))?;
+                    families.push(family);
+                }
+                FontSource::Family(family) => families.push(family.clone()),
+            }
+        }
+
+        let fonts = Fonts { database, families };
+        for family in &fonts.families {
+            fonts.face(family)?;
+        }
+        Ok(fonts)
+    }
+}
+
+/// Loaded fonts, ready to be used to rasterize and to embed into documents.
+#[derive(Clone, Debug)]
+pub struct Fonts {
+    database: fontdb::Database,
+    /// the families of the configured fonts, in order
+    families: Vec<String>,
+}
+
+impl Fonts {
+    pub fn database(&self) -> &fontdb::Database {
+        &self.database
+    }
+
+    /// The family text is written in, if a font was configured.
+    pub fn family(&self) -> Option<&str> {
+        self.families.first().map(String::as_str)
+    }
+
+    fn face(&self, family: &str) -> Result<ID, FontError> {
+        self.database
+            .query(&Query {
+                families: &[Family::Name(family)],
+                ..Query::default()
+            })
+            .ok_or_else(|| FontError::UnknownFamily(family.to_string()))
+    }
+
+    /// The TrueType data of `family`, cut down to the glyphs of the characters
+    /// in `text` when there is one.
+    pub fn font_data(&self, family: &str, text: Option<&str>) -> Result<Vec<u8>, FontError> {
+        let id = self.face(family)?;
+        self.database
+            .with_face_data(id, |data, index| match text {
+                None => Ok(data.to_vec()),
+                Some(text) => {
+                    let subset_error = |error| FontError::Subset {
+                        family: family.to_string(),
+                        error,
+                    };
+                    let face = ttf_parser::Face::parse(data, index)
+                        .map_err(|_| subset_error(subsetter::Error::InvalidData))?;
+                    // glyph 0 is drawn for characters the font doesn't have
+                    let glyphs: Vec<u16> = std::iter::once(0)
+                        .chain(
+                            text.chars()
+                                .collect::<BTreeSet<_>>()
+                                .into_iter()
+                                .filter_map(|c| face.glyph_index(c))
+                                .map(|glyph| glyph.0),
+                        )
+                        .collect();
+                    subsetter::subset(data, index, subsetter::Profile::pdf(&glyphs))
+                        .map_err(subset_error)
+                }
+            })
+            .ok_or_else(|| FontError::UnknownFamily(family.to_string()))?
+    }
+
+    /// A `@font-face` rule with the font of `family` inlined as base64, so the
+    /// document shows it wherever it is opened. Only the characters in `text`
+    /// are kept when there is one.
+    pub fn font_face(&self, family: &str, text: Option<&str>) -> Result<String, FontError> {
+        let data = self.font_data(family, text)?;
+        Ok(format!(
+            "@font-face {{\n    font-family: \"{}\";\n    src: url(data:font/ttf;base64,{}) format(\"truetype\");\n}}\n",
+            family,
+            STANDARD.encode(data)
+        ))
+    }
+
+    /// A `<style>` that writes every text in the configured family, with the
+    /// font inlined when `embed` is set. `text` is used to subset the font.
+    pub fn style(&self, embed: bool, text: Option<&str>) -> Result<Option<Style>, FontError> {
+        let Some(family) = self.family() else {
+            return Ok(None);
+        };
+        let mut css = String::new();
+        if embed {
+            css.push_str(&self.font_face(family, text)?);
+        }
+        css.push_str(&format!("text {{ font-family: \"{}\"; }}\n", family));
+        Ok(Some(Style::new(css).set("type", "text/css")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundled() -> FontConfig {
+        FontConfig::new().system_fonts(false)
+    }
+
+    #[test]
+    fn test_bundled_family() {
+        let fonts = bundled()
+            .font(FontSource::Family(TENGWAR_ANNATAR.to_string()))
+            .load()
+            .unwrap();
+        assert_eq!(fonts.family(), Some(TENGWAR_ANNATAR));
+        let style = fonts.style(false, None).unwrap().unwrap().to_string();
+        assert!(style.contains("font-family: \"Tengwar Annatar\""));
+        assert!(!style.contains("base64"));
+    }
+
+    #[test]
+    fn test_subset_is_smaller() {
+        let fonts = bundled().load().unwrap();
+        let full = fonts.font_data(TENGWAR_ANNATAR, None).unwrap();
+        let subset = fonts.font_data(TENGWAR_ANNATAR, Some("magic")).unwrap();
+        assert!(subset.len() < full.len());
+        let face = ttf_parser::Face::parse(&subset, 0).unwrap();
+        assert!(face.glyph_index('m').is_some());
+
+        let face_rule = fonts.font_face(TENGWAR_ANNATAR, Some("magic")).unwrap();
+        assert!(face_rule.contains(&STANDARD.encode(&subset)));
+    }
+
+    #[test]
+    fn test_font_file() {
+        let fonts = FontConfig::new()
+            .system_fonts(false)
+            .bundled_fonts(false)
+            .font(FontSource::guess(
+                "resources/fonts/TengwarAnnatarAltBoldItalic-YzDo.ttf",
+            ))
+            .load()
+            .unwrap();
+        assert!(fonts.family().unwrap().starts_with(TENGWAR_ANNATAR));
+    }
+
+    #[test]
+    fn test_guess() {
+        assert_eq!(
+            FontSource::guess("missing.TTF"),
+            FontSource::Path("missing.TTF".into())
+        );
+        assert_eq!(
+            FontSource::guess("Tengwar Annatar"),
+            FontSource::Family(TENGWAR_ANNATAR.to_string())
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let load = |source| bundled().font(source).load().unwrap_err().to_string();
+        assert_eq!(
+            load(FontSource::Family("Comic Sans".to_string())),
+            "no font named `Comic Sans` is loaded"
+        );
+        assert!(load(FontSource::Path("missing.ttf".into()))
+            .starts_with("can't read font `missing.ttf`: "));
+        assert_eq!(
+            load(FontSource::Path(
+                "resources/fonts/TengwarAnnatarSource.txt".into()
+            )),
+            "`resources/fonts/TengwarAnnatarSource.txt` is not a font"
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use phf::{phf_map, Map};
+
+use crate::script::{in_table, read_longest, Script};
+
+/// The separator written between words.
+const WORD_SEPARATOR: char = '᛫';
+const WORD_SEPARATOR_STR: &str = "᛫";
+
+/// The runes of the Elder Futhark for Latin letters and digraphs, lowercase.
+/// Letters the futhark has no rune for are written like their sound, `ï` is
+/// the usual transliteration of eihwaz.
+const RUNES: Map<&'static str, &'static str> = phf_map! {
+    "a" => "ᚨ",
+    "b" => "ᛒ",
+    "c" => "ᚲ",
+    "d" => "ᛞ",
+    "e" => "ᛖ",
+    "f" => "ᚠ",
+    "g" => "ᚷ",
+    "h" => "ᚺ",
+    "i" => "ᛁ",
+    "j" => "ᛃ",
+    "k" => "ᚲ",
+    "l" => "ᛚ",
+    "m" => "ᛗ",
+    "n" => "ᚾ",
+    "o" => "ᛟ",
+    "p" => "ᛈ",
+    "q" => "ᚲ",
+    "r" => "ᚱ",
+    "s" => "ᛊ",
+    "t" => "ᛏ",
+    "u" => "ᚢ",
+    "v" => "ᚹ",
+    "w" => "ᚹ",
+    "x" => "ᚲᛊ",
+    "y" => "ᛃ",
+    "z" => "ᛉ",
+    "th" => "ᚦ",
+    "ng" => "ᛜ",
+    "ï" => "ᛇ",
+    " " => "᛫",
+    "." => "᛬",
+    "," => "᛬",
+};
+
+/// The 24 runes of the Elder Futhark, in the Unicode Runic block.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ElderFuthark;
+
+impl Script for ElderFuthark {
+    fn name(&self) -> &'static str {
+        "futhark"
+    }
+
+    /// Runes for the longest digraph or letter at every position, anything
+    /// else is written as a word separator.
+    fn transliterate(&self, text: &str) -> String {
+        read_longest(text, &RUNES, 2)
+            .into_iter()
+            .map(|(_, runes)| runes.unwrap_or(WORD_SEPARATOR_STR))
+            .collect()
+    }
+
+    fn can_write(&self, c: char) -> bool {
+        in_table(&RUNES, c)
+    }
+
+    fn glyphs(&self) -> Vec<char> {
+        let mut glyphs: Vec<char> = RUNES.values().flat_map(|runes| runes.chars()).collect();
+        glyphs.push(WORD_SEPARATOR);
+        glyphs.sort();
+        glyphs.dedup();
+        glyphs
+    }
+
+    fn font(&self) -> &'static str {
+        "Noto Sans Runic"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transliterate() {
+        assert_eq!(ElderFuthark.transliterate("Thing"), "ᚦᛁᛜ");
+        assert_eq!(ElderFuthark.transliterate("fox, 1"), "ᚠᛟᚲᛊ᛬᛫᛫");
+        // all 24 runes are written
+        let runes = ElderFuthark.glyphs();
+        assert_eq!(runes.iter().filter(|&&c| c < '᛫').count(), 24);
+    }
+}
@@ -0,0 +1,220 @@
+use svg::node::element::path::Data;
+
+pub type Point = (f64, f64);
+
+/// 0.5 gives the centripetal variant, which never forms cusps or loops
+/// within a segment
+const ALPHA: f64 = 0.5;
+
+/// Appends a Catmull–Rom spline through `points` to `data` as cubic Bézier
+/// curves. It runs from the second to the second to last point, the outer
+/// points only steer the direction of its ends, so with fewer than four
+/// points nothing is appended.
+pub fn to_catmul_rom_spline(mut data: Data, points: &[Point]) -> Data {
+    if points.len() < 4 {
+        return data;
+    }
+    data = data.move_to(points[1]);
+    for [_, q1, q2, q3] in catmul_rom_curves(points) {
+        data = data.cubic_curve_to((q1.0, q1.1, q2.0, q2.1, q3.0, q3.1));
+    }
+    data
+}
+
+/// The cubic Bézier curves `to_catmul_rom_spline` draws, each as its start,
+/// its two control points and its end. There are none for fewer than four
+/// points.
+pub fn catmul_rom_curves(points: &[Point]) -> Vec<[Point; 4]> {
+    let mut curves = vec![];
+    let mut t_i = vec![0.0];
+    for i in 1..points.len() {
+        let (x1, y1) = points[i - 1];
+        let (x2, y2) = points[i];
+        let d = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt().powf(ALPHA);
+        t_i.push(t_i[i - 1] + d);
+    }
+
+    for i in 1..points.len().saturating_sub(2) {
+        let (x0, y0) = points[i - 1];
+        let (x1, y1) = points[i];
+        let (x2, y2) = points[i + 1];
+        let (x3, y3) = points[i + 2];
+
+        let t0 = t_i[i - 1];
+        let t1 = t_i[i];
+        let t2 = t_i[i + 1];
+        let t3 = t_i[i + 2];
+
+        let c1 = (t2 - t1) / (t2 - t0);
+        let c2 = (t1 - t0) / (t2 - t0);
+
+        let d1 = (t3 - t2) / (t3 - t1);
+        let d2 = (t2 - t1) / (t3 - t1);
+
+        let m1 = (
+            (t2 - t1) * (c1 * (x1 - x0) / (t1 - t0) + c2 * (x2 - x1) / (t2 - t1)),
+            (t2 - t1) * (c1 * (y1 - y0) / (t1 - t0) + c2 * (y2 - y1) / (t2 - t1)),
+        );
+
+        let m2 = (
+            (t2 - t1) * (d1 * (x2 - x1) / (t2 - t1) + d2 * (x3 - x2) / (t3 - t2)),
+            (t2 - t1) * (d1 * (y2 - y1) / (t2 - t1) + d2 * (y3 - y2) / (t3 - t2)),
+        );
+
+        // let q0 = (x1, x2);
+        let q1 = (
+            x1 + m1.0 / 3.0,
+            y1 + m1.1 / 3.0,
+        );
+        let q2 = (
+            x2 - m2.0 / 3.0,
+            y2 - m2.1 / 3.0,
+        );
+        let q3 = (x2, y2);
+        curves.push([(x1, y1), q1, q2, q3]);
+    }
+
+    curves
+}
+
+/// The point at `t`, from 0 at the start to 1 at the end, of a cubic Bézier
+/// curve.
+pub fn cubic_bezier([p0, p1, p2, p3]: [Point; 4], t: f64) -> Point {
+    let s = 1.0 - t;
+    let weights = [s * s * s, 3.0 * s * s * t, 3.0 * s * t * t, t * t * t];
+    let x = weights[0] * p0.0 + weights[1] * p1.0 + weights[2] * p2.0 + weights[3] * p3.0;
+    let y = weights[0] * p0.1 + weights[1] * p1.1 + weights[2] * p2.1 + weights[3] * p3.1;
+    (x, y)
+}
+
+/// A path of straight segments, measured along its length to place things on
+/// it. Curves are measured by sampling them finely.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polyline {
+    points: Vec<Point>,
+    /// the distance along the line to every point
+    distances: Vec<f64>,
+}
+
+impl Polyline {
+    /// Repeated points are skipped, there has to be at least one.
+    pub fn new(points: impl IntoIterator<Item = Point>) -> Self {
+        let mut line = Polyline {
+            points: vec![],
+            distances: vec![],
+        };
+        for point in points {
+            match line.points.last() {
+                None => line.distances.push(0.0),
+                Some(&last) if last == point => continue,
+                Some(&last) => {
+                    let distance = line.distances[line.distances.len() - 1];
+                    line.distances
+                        .push(distance + (point.0 - last.0).hypot(point.1 - last.1));
+                }
+            }
+            line.points.push(point);
+        }
+        assert!(!line.points.is_empty(), "a polyline needs a point");
+        line
+    }
+
+    pub fn length(&self) -> f64 {
+        self.distances[self.distances.len() - 1]
+    }
+
+    /// The point `distance` along the line and the direction the line runs
+    /// there as a unit vector. Directions are blended between the segments
+    /// meeting at a point, so a sampled curve turns smoothly. Distances past
+    /// either end are taken at that end.
+    pub fn at(&self, distance: f64) -> (Point, Point) {
+        if self.points.len() == 1 {
+            return (self.points[0], (1.0, 0.0));
+        }
+        let last = self.points.len() - 1;
+        let distance = distance.clamp(0.0, self.length());
+        // the segment that ends at or after `distance`
+        let end = self
+            .distances
+            .partition_point(|&d| d < distance)
+            .clamp(1, last);
+        let segment = |end: usize| {
+            let (from, to) = (self.points[end - 1], self.points[end]);
+            let length = self.distances[end] - self.distances[end - 1];
+            ((to.0 - from.0) / length, (to.1 - from.1) / length)
+        };
+        let direction = segment(end);
+        let (first_point, last_point) = (self.points[0], self.points[last]);
+        let closed = (first_point.0 - last_point.0).hypot(first_point.1 - last_point.1) < 1e-9;
+        // the direction at a point halfway between its two segments, the
+        // ends of a closed line are the same point
+        let at_point = |point: usize| {
+            let (before, after) = if 0 < point && point < last {
+                (segment(point), segment(point + 1))
+            } else if closed {
+                (segment(last), segment(1))
+            } else {
+                return direction;
+            };
+            normalize((before.0 + after.0, before.1 + after.1)).unwrap_or(after)
+        };
+
+        let from = self.points[end - 1];
+        let along = distance - self.distances[end - 1];
+        let t = along / (self.distances[end] - self.distances[end - 1]);
+        let (start, stop) = (at_point(end - 1), at_point(end));
+        let blended = (
+            start.0 + (stop.0 - start.0) * t,
+            start.1 + (stop.1 - start.1) * t,
+        );
+        (
+            (from.0 + direction.0 * along, from.1 + direction.1 * along),
+            normalize(blended).unwrap_or(direction),
+        )
+    }
+}
+
+/// `vector` scaled to length 1, `None` if it has none.
+fn normalize((x, y): Point) -> Option<Point> {
+    let length = x.hypot(y);
+    (length > f64::EPSILON).then(|| (x / length, y / length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use svg::node::element::path::Command;
+
+    #[test]
+    fn test_spline_passes_through_inner_points() {
+        let points = [(0.0, 0.0), (1.0, 1.0), (2.0, 0.0), (3.0, 1.0), (4.0, 0.0)];
+        let data = to_catmul_rom_spline(Data::new(), &points);
+        let ends: Vec<_> = data
+            .iter()
+            .map(|command| match command {
+                Command::Move(_, parameters) => (parameters[0], parameters[1]),
+                Command::CubicCurve(_, parameters) => (parameters[4], parameters[5]),
+                _ => panic!("unexpected command {:?}", command),
+            })
+            .collect();
+        assert_eq!(ends, vec![(1.0, 1.0), (2.0, 0.0), (3.0, 1.0)]);
+
+        let curves = catmul_rom_curves(&points);
+        assert_eq!(cubic_bezier(curves[0], 0.0), (1.0, 1.0));
+        assert_eq!(cubic_bezier(curves[1], 1.0), (3.0, 1.0));
+    }
+
+    #[test]
+    fn test_polyline() {
+        let line = Polyline::new([(0.0, 0.0), (3.0, 0.0), (3.0, 0.0), (3.0, 4.0)]);
+        assert_eq!(line.length(), 7.0);
+        assert_eq!(line.at(1.0).0, (1.0, 0.0));
+        assert_eq!(line.at(5.0).0, (3.0, 2.0));
+        // halfway between the corner, where it points diagonally, and the end
+        let (x, y) = line.at(5.0).1;
+        assert!((x.hypot(y) - 1.0).abs() < 1e-9);
+        assert!(x > 0.0 && y > x);
+        assert_eq!(line.at(-1.0), ((0.0, 0.0), (1.0, 0.0)));
+        assert_eq!(line.at(10.0), ((3.0, 4.0), (0.0, 1.0)));
+    }
+}
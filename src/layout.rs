@@ -0,0 +1,618 @@
+use std::f64::consts::PI;
+
+use svg::node::element::path::Data;
+use svg::node::element::{Circle, Group, Line, Path, Text, TextPath};
+use svg::Document;
+
+use crate::evaluator::Child;
+use crate::carve::{carve, CarveOptions, Stem};
+use crate::geometry::Point;
+use crate::ogham::{parse_ogham, OghamScript};
+use crate::script::{Script, Unmappable, UnmappableError};
+use crate::ast::LambdaExpression;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayoutOptions {
+    /// radius of the dot drawn for a variable occurrence
+    pub variable_radius: f64,
+    /// space between an abstraction circle and the body nested inside it
+    pub padding: f64,
+    /// space between the function and argument of an application
+    pub gap: f64,
+    pub stroke_width: f64,
+    /// space between the outermost circle and the edge of the document
+    pub margin: f64,
+    /// color of the redex highlighted by `Diagram::with_redex`
+    pub highlight: &'static str,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        LayoutOptions {
+            variable_radius: 4.0,
+            padding: 10.0,
+            gap: 12.0,
+            stroke_width: 1.0,
+            margin: 20.0,
+            highlight: "crimson",
+        }
+    }
+}
+
+/// The circle drawn for a `LambdaExpression::Abstraction`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BinderCircle {
+    pub name: String,
+    pub center: Point,
+    pub radius: f64,
+    pub highlighted: bool,
+    /// written above the circle, like the type of the abstraction
+    pub annotation: Option<String>,
+}
+
+/// A variable occurrence, `binder` is the index of the circle that binds it
+/// or `None` when the variable is free.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VariableNode {
+    pub name: String,
+    pub center: Point,
+    pub binder: Option<usize>,
+    pub highlighted: bool,
+}
+
+/// The connection drawn from the function to the argument of a
+/// `LambdaExpression::Application`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApplicationLink {
+    pub from: Point,
+    pub to: Point,
+    pub highlighted: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagram {
+    pub binders: Vec<BinderCircle>,
+    pub variables: Vec<VariableNode>,
+    pub applications: Vec<ApplicationLink>,
+    /// radius of the disc, centered on the origin, that contains the whole diagram
+    pub radius: f64,
+    options: LayoutOptions,
+    /// path to the sub expression that is highlighted
+    redex: Option<Vec<Child>>,
+}
+
+/// Size of every sub expression, computed bottom up before anything is placed.
+struct Measured<'a> {
+    expr: &'a LambdaExpression,
+    radius: f64,
+    children: Vec<Measured<'a>>,
+}
+
+fn measure<'a>(expr: &'a LambdaExpression, options: &LayoutOptions) -> Measured<'a> {
+    match expr {
+        LambdaExpression::Variable(_) => Measured {
+            expr,
+            radius: options.variable_radius,
+            children: vec![],
+        },
+        LambdaExpression::Abstraction(_, body) => {
+            let body = measure(body, options);
+            Measured {
+                expr,
+                radius: body.radius + options.padding,
+                children: vec![body],
+            }
+        }
+        LambdaExpression::Application(function, argument) => {
+            let function = measure(function, options);
+            let argument = measure(argument, options);
+            Measured {
+                expr,
+                radius: function.radius + argument.radius + options.gap / 2.0,
+                children: vec![function, argument],
+            }
+        }
+    }
+}
+
+impl Diagram {
+    pub fn new(expr: &LambdaExpression, options: &LayoutOptions) -> Self {
+        Self::layout(expr, options, None)
+    }
+
+    /// Lays out `expr` with the sub expression at `redex`, as found by
+    /// `evaluator::step_redex`, drawn in the highlight color.
+    pub fn with_redex(expr: &LambdaExpression, options: &LayoutOptions, redex: &[Child]) -> Self {
+        Self::layout(expr, options, Some(redex.to_vec()))
+    }
+
+    fn layout(expr: &LambdaExpression, options: &LayoutOptions, redex: Option<Vec<Child>>) -> Self {
+        let measured = measure(expr, options);
+        let mut diagram = Diagram {
+            binders: vec![],
+            variables: vec![],
+            applications: vec![],
+            radius: measured.radius,
+            options: *options,
+            redex,
+        };
+        diagram.place(&measured, (0.0, 0.0), &mut vec![], &mut vec![], false);
+        diagram
+    }
+
+    /// `scope` maps the names currently in scope to their binder circle,
+    /// innermost binder last so shadowing resolves to the closest circle.
+    /// `path` leads from the root to `measured`.
+    fn place(
+        &mut self,
+        measured: &Measured,
+        center: Point,
+        scope: &mut Vec<(String, usize)>,
+        path: &mut Vec<Child>,
+        highlighted: bool,
+    ) {
+        let highlighted = highlighted || self.redex.as_ref() == Some(path);
+        match measured.expr {
+            LambdaExpression::Variable(name) => {
+                let binder = scope
+                    .iter()
+                    .rev()
+                    .find(|(bound, _)| bound == name)
+                    .map(|(_, index)| *index);
+                self.variables.push(VariableNode {
+                    name: name.clone(),
+                    center,
+                    binder,
+                    highlighted,
+                });
+            }
+            LambdaExpression::Abstraction(name, _) => {
+                self.binders.push(BinderCircle {
+                    name: name.clone(),
+                    center,
+                    radius: measured.radius,
+                    highlighted,
+                    annotation: None,
+                });
+                scope.push((name.clone(), self.binders.len() - 1));
+                path.push(Child::Body);
+                self.place(&measured.children[0], center, scope, path, highlighted);
+                path.pop();
+                scope.pop();
+            }
+            LambdaExpression::Application(_, _) => {
+                let (function, argument) = (&measured.children[0], &measured.children[1]);
+                let function_center = (center.0 - measured.radius + function.radius, center.1);
+                let argument_center = (center.0 + measured.radius - argument.radius, center.1);
+                self.applications.push(ApplicationLink {
+                    from: (function_center.0 + function.radius, center.1),
+                    to: (argument_center.0 - argument.radius, center.1),
+                    highlighted,
+                });
+                path.push(Child::Function);
+                self.place(function, function_center, scope, path, highlighted);
+                path.pop();
+                path.push(Child::Argument);
+                self.place(argument, argument_center, scope, path, highlighted);
+                path.pop();
+            }
+        }
+    }
+
+    /// Annotates the binder circles in order, as many as there are labels.
+    /// `types::TypeEnvironment::abstraction_types` gives their types in that
+    /// order.
+    pub fn annotate<T: ToString>(&mut self, labels: impl IntoIterator<Item = T>) {
+        for (binder, label) in self.binders.iter_mut().zip(labels) {
+            binder.annotation = Some(label.to_string());
+        }
+    }
+
+    /// The point on the binding circle a variable is linked to, straight
+    /// above the variable so links never run along the horizontal
+    /// application lines.
+    pub fn binding_point(&self, variable: &VariableNode) -> Option<Point> {
+        let binder = &self.binders[variable.binder?];
+        let dx = variable.center.0 - binder.center.0;
+        let dy = (binder.radius.powi(2) - dx.powi(2)).max(0.0).sqrt();
+        Some((variable.center.0, binder.center.1 - dy))
+    }
+
+    /// All elements of the diagram, centered on the origin.
+    pub fn to_group(&self) -> Group {
+        let options = &self.options;
+        let mut group = Group::new()
+            .set("fill", "none")
+            .set("stroke", "black")
+            .set("stroke-width", options.stroke_width);
+        let color = |highlighted| {
+            if highlighted {
+                options.highlight
+            } else {
+                "black"
+            }
+        };
+
+        for binder in &self.binders {
+            group = group.add(
+                Circle::new()
+                    .set("cx", binder.center.0)
+                    .set("cy", binder.center.1)
+                    .set("r", binder.radius)
+                    .set("stroke", color(binder.highlighted)),
+            );
+            if let Some(annotation) = &binder.annotation {
+                group = group.add(
+                    Text::new(annotation.clone())
+                        .set("x", binder.center.0)
+                        .set(
+                            "y",
+                            binder.center.1 - binder.radius - options.variable_radius / 2.0,
+                        )
+                        .set("text-anchor", "middle")
+                        .set("font-size", options.variable_radius * 2.0)
+                        .set("fill", color(binder.highlighted))
+                        .set("stroke", "none"),
+                );
+            }
+        }
+
+        for link in &self.applications {
+            let color = color(link.highlighted);
+            group = group
+                .add(
+                    Line::new()
+                        .set("x1", link.from.0)
+                        .set("y1", link.from.1)
+                        .set("x2", link.to.0)
+                        .set("y2", link.to.1)
+                        .set("stroke", color),
+                )
+                .add(
+                    Circle::new()
+                        .set("cx", link.to.0)
+                        .set("cy", link.to.1)
+                        .set("r", options.variable_radius / 2.0)
+                        .set("fill", color)
+                        .set("stroke", color),
+                );
+        }
+
+        for variable in &self.variables {
+            let color = color(variable.highlighted);
+            let dot = Circle::new()
+                .set("cx", variable.center.0)
+                .set("cy", variable.center.1)
+                .set("r", options.variable_radius)
+                .set("stroke", color);
+
+            let Some(binding_point) = self.binding_point(variable) else {
+                // free variables are hollow and labeled with their name
+                group = group.add(dot.set("fill", "white")).add(
+                    Text::new(variable.name.clone())
+                        .set("x", variable.center.0)
+                        .set("y", variable.center.1 - options.variable_radius * 1.5)
+                        .set("text-anchor", "middle")
+                        .set("font-size", options.variable_radius * 3.0)
+                        .set("fill", color)
+                        .set("stroke", "none"),
+                );
+                continue;
+            };
+
+            group = group
+                .add(
+                    Line::new()
+                        .set("x1", variable.center.0)
+                        .set("y1", variable.center.1)
+                        .set("x2", binding_point.0)
+                        .set("y2", binding_point.1)
+                        .set("stroke-width", options.stroke_width / 2.0)
+                        .set("stroke", color),
+                )
+                .add(dot.set("fill", color))
+                .add(
+                    Circle::new()
+                        .set("cx", binding_point.0)
+                        .set("cy", binding_point.1)
+                        .set("r", options.variable_radius / 2.0)
+                        .set("fill", color)
+                        .set("stroke", color),
+                );
+        }
+
+        group
+    }
+
+    /// A ring around the outermost circle with a dot for every `0` and a dash
+    /// for every `1` in `bits`, like the encoding of the term from
+    /// `binary::encode`. It starts at the top and goes clockwise, in the
+    /// margin around the diagram.
+    pub fn bit_ring(&self, bits: &[bool]) -> Group {
+        let options = &self.options;
+        let radius = self.radius + options.margin / 2.0;
+        let segment = 2.0 * PI / bits.len() as f64;
+        let point = |angle: f64| {
+            let angle = angle - PI / 2.0;
+            (radius * angle.cos(), radius * angle.sin())
+        };
+
+        let mut group = Group::new()
+            .set("fill", "none")
+            .set("stroke", "black")
+            .set("stroke-width", options.stroke_width);
+        for (index, &bit) in bits.iter().enumerate() {
+            let start = index as f64 * segment;
+            if bit {
+                let (from, to) = (point(start + segment * 0.15), point(start + segment * 0.85));
+                let large_arc = if segment * 0.7 > PI { 1 } else { 0 };
+                let data = Data::new()
+                    .move_to(from)
+                    .elliptical_arc_to((radius, radius, 0, large_arc, 1, to.0, to.1));
+                group = group.add(Path::new().set("d", data));
+            } else {
+                let center = point(start + segment / 2.0);
+                group = group.add(
+                    Circle::new()
+                        .set("cx", center.0)
+                        .set("cy", center.1)
+                        .set("r", options.stroke_width)
+                        .set("fill", "black"),
+                );
+            }
+        }
+        group
+    }
+
+    /// `text` in `script`, written around the outermost circle at the outer
+    /// edge of the margin so it clears the bit ring, starting at the top and
+    /// going clockwise. Characters the script has no letter for are handled
+    /// by `unmappable`.
+    ///
+    /// The text runs along a path with the given `id`, which has to be unique
+    /// in the document the inscription ends up in.
+    pub fn inscription(
+        &self,
+        id: &str,
+        text: &str,
+        script: &dyn Script,
+        unmappable: Unmappable,
+    ) -> Result<Group, UnmappableError> {
+        let options = &self.options;
+        let font_size = options.margin * 0.3;
+        let radius = self.radius + options.margin * 0.6;
+        // the whole ring clockwise from the top, as two half circles
+        let ring = Data::new()
+            .move_to((0.0, -radius))
+            .elliptical_arc_to((radius, radius, 0, 0, 1, 0.0, radius))
+            .elliptical_arc_to((radius, radius, 0, 0, 1, 0.0, -radius))
+            .close();
+
+        let written = script.transliterate_with(text, unmappable)?;
+        Ok(Group::new()
+            .add(
+                Path::new()
+                    .set("id", id)
+                    .set("d", ring)
+                    .set("fill", "none")
+                    .set("stroke", "none"),
+            )
+            .add(
+                // inline, so it wins over a `text` rule setting the font of
+                // the rest of the drawing
+                Text::new("")
+                    .set("style", format!("font-family: '{}'", script.font()))
+                    .set("font-size", font_size)
+                    .set("fill", "black")
+                    .add(TextPath::new(written).set("href", format!("#{}", id))),
+            ))
+    }
+
+    /// `text` in Ogham around the outermost circle like `inscription`, carved
+    /// as strokes along a circle instead of written in a font, see
+    /// `carve::carve`. Letters that don't fit around it are left out, and
+    /// returned is how many.
+    pub fn carved_inscription(
+        &self,
+        text: &str,
+        unmappable: Unmappable,
+    ) -> Result<(Group, usize), UnmappableError> {
+        let options = &self.options;
+        let written = OghamScript.transliterate_with(text, unmappable)?;
+        let letters = parse_ogham(&written).expect("transliterated Ogham reads back");
+        let stem = Stem::Circle {
+            center: (0.0, 0.0),
+            radius: self.radius + options.margin * 0.6,
+        };
+        let (group, carved) = carve(
+            &letters,
+            &stem,
+            &CarveOptions {
+                score: options.margin * 0.3,
+                stroke_width: options.stroke_width,
+                ..CarveOptions::default()
+            },
+        )
+        .expect("a circle is a valid stem");
+        Ok((group, letters.len() - carved))
+    }
+
+    /// A standalone document whose viewBox fits the whole diagram.
+    pub fn to_document(&self) -> Document {
+        let extent = self.radius + self.options.margin;
+        Document::new()
+            .set("viewBox", (-extent, -extent, 2.0 * extent, 2.0 * extent))
+            .add(self.to_group())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TypeEnvironment;
+
+    fn var(name: &str) -> LambdaExpression {
+        LambdaExpression::Variable(name.to_string())
+    }
+
+    fn abs(name: &str, body: LambdaExpression) -> LambdaExpression {
+        LambdaExpression::Abstraction(name.to_string(), Box::new(body))
+    }
+
+    fn app(function: LambdaExpression, argument: LambdaExpression) -> LambdaExpression {
+        LambdaExpression::Application(Box::new(function), Box::new(argument))
+    }
+
+    #[test]
+    fn test_identity() {
+        let options = LayoutOptions::default();
+        let diagram = Diagram::new(&abs("x", var("x")), &options);
+        assert_eq!(diagram.binders.len(), 1);
+        assert_eq!(
+            diagram.binders[0].radius,
+            options.variable_radius + options.padding
+        );
+        assert_eq!(diagram.variables[0].binder, Some(0));
+        assert_eq!(
+            diagram.binding_point(&diagram.variables[0]),
+            Some((0.0, -diagram.binders[0].radius))
+        );
+    }
+
+    #[test]
+    fn test_shadowing_binds_innermost() {
+        let diagram = Diagram::new(&abs("x", abs("x", var("x"))), &LayoutOptions::default());
+        assert_eq!(diagram.variables[0].binder, Some(1));
+    }
+
+    #[test]
+    fn test_free_variable() {
+        let diagram = Diagram::new(&abs("x", var("y")), &LayoutOptions::default());
+        assert_eq!(diagram.variables[0].binder, None);
+        assert_eq!(diagram.binding_point(&diagram.variables[0]), None);
+    }
+
+    #[test]
+    fn test_application_side_by_side() {
+        let options = LayoutOptions::default();
+        let diagram = Diagram::new(&app(abs("x", var("x")), var("y")), &options);
+        let function = &diagram.binders[0];
+        let argument = diagram.variables.iter().find(|v| v.name == "y").unwrap();
+        assert!(function.center.0 < argument.center.0);
+        let link = &diagram.applications[0];
+        assert_eq!(link.from.0, function.center.0 + function.radius);
+        assert_eq!(link.to.0 - link.from.0, options.gap);
+        assert_eq!(
+            diagram.radius,
+            function.radius + options.variable_radius + options.gap / 2.0
+        );
+    }
+
+    #[test]
+    fn test_children_inside_parent() {
+        // λf.(λx.x x)(λx.f (x x))
+        let expr = abs(
+            "f",
+            app(
+                abs("x", app(var("x"), var("x"))),
+                abs("x", app(var("f"), app(var("x"), var("x")))),
+            ),
+        );
+        let diagram = Diagram::new(&expr, &LayoutOptions::default());
+        let outer = &diagram.binders[0];
+        for binder in &diagram.binders[1..] {
+            let distance = ((binder.center.0 - outer.center.0).powi(2)
+                + (binder.center.1 - outer.center.1).powi(2))
+            .sqrt();
+            assert!(distance + binder.radius <= outer.radius);
+        }
+        assert!(diagram.variables.iter().all(|v| v.binder.is_some()));
+    }
+
+    #[test]
+    fn test_with_redex_highlights_subtree() {
+        // λf.f ((λx.x) f), the redex is the argument of the outer application
+        let expr = abs("f", app(var("f"), app(abs("x", var("x")), var("f"))));
+        let diagram = Diagram::with_redex(
+            &expr,
+            &LayoutOptions::default(),
+            &[Child::Body, Child::Argument],
+        );
+        let highlighted_binders: Vec<_> = diagram
+            .binders
+            .iter()
+            .map(|binder| (binder.name.as_str(), binder.highlighted))
+            .collect();
+        assert_eq!(highlighted_binders, vec![("f", false), ("x", true)]);
+        assert_eq!(
+            diagram
+                .applications
+                .iter()
+                .filter(|link| link.highlighted)
+                .count(),
+            1
+        );
+        assert_eq!(
+            diagram
+                .variables
+                .iter()
+                .filter(|variable| variable.highlighted)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_annotate_with_types() {
+        let expr = abs("x", abs("y", var("x")));
+        let mut diagram = Diagram::new(&expr, &LayoutOptions::default());
+        diagram.annotate(TypeEnvironment::new().abstraction_types(&expr).unwrap());
+        let annotations: Vec<_> = diagram
+            .binders
+            .iter()
+            .map(|binder| binder.annotation.as_deref())
+            .collect();
+        assert_eq!(annotations, vec![Some("a -> b -> a"), Some("a -> b")]);
+        assert!(diagram.to_group().to_string().contains("a -&gt; b -&gt; a"));
+    }
+
+    #[test]
+    fn test_bit_ring() {
+        let diagram = Diagram::new(&abs("x", var("x")), &LayoutOptions::default());
+        // `0010`, three dots and a dash
+        let ring = diagram.bit_ring(&[false, false, true, false]).to_string();
+        assert_eq!(ring.matches("<circle").count(), 3);
+        assert_eq!(ring.matches("<path").count(), 1);
+    }
+
+    #[test]
+    fn test_inscription() {
+        let diagram = Diagram::new(&abs("x", var("x")), &LayoutOptions::default());
+        let ogham = diagram
+            .inscription("ring", "ab", &OghamScript, Unmappable::Space)
+            .unwrap()
+            .to_string();
+        assert!(ogham.contains("style=\"font-family: &apos;Noto Sans Ogham&apos;\""));
+        assert!(ogham.contains("id=\"ring\""));
+        assert!(ogham.contains("href=\"#ring\""));
+        assert!(ogham.contains(">᚛ᚐᚁ᚜</textPath>"));
+        assert!(diagram
+            .inscription("ring", "a b", &OghamScript, Unmappable::Error)
+            .is_ok());
+        assert!(diagram
+            .inscription("ring", "a 1", &OghamScript, Unmappable::Error)
+            .is_err());
+
+        let (carved, left_out) = diagram.carved_inscription("ab", Unmappable::Space).unwrap();
+        let carved = carved.to_string();
+        assert_eq!(left_out, 0);
+        assert!(!carved.contains("<text"));
+        // the stem runs through the middle of the margin
+        assert!(carved.contains("M0,-26 A26,26,0,0,1,0,26"));
+        let long = "ab".repeat(20);
+        let (_, left_out) = diagram
+            .carved_inscription(&long, Unmappable::Space)
+            .unwrap();
+        assert!(left_out > 0);
+    }
+}
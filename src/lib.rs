@@ -0,0 +1,29 @@
+//! Draws lambda calculus terms as magic circles.
+//!
+//! `parser::parse` reads a term into an `ast::LambdaExpression`, `layout`
+//! and `tromp` place it on the page and `render::Renderer` turns it into an
+//! SVG document or a PNG image.
+
+pub mod ast;
+pub mod binary;
+pub mod carve;
+pub mod church;
+pub mod combinators;
+pub mod de_bruijn;
+pub mod definitions;
+pub mod evaluator;
+pub mod fonts;
+pub mod futhark;
+pub mod geometry;
+pub mod layout;
+pub mod ogham;
+pub mod parse_error;
+pub mod parse_trace;
+pub mod parser;
+pub mod pretty_printer;
+pub mod render;
+pub mod script;
+pub mod tengwar;
+pub mod theban;
+pub mod tromp;
+pub mod types;
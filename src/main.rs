@@ -1,65 +1,226 @@
-use std::collections::HashMap;
-use std::io::BufWriter;
-use itertools::Itertools;
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::process::ExitCode;
 
-use resvg::usvg::fontdb::{Family, Query};
-use svg::node::element::path::Data;
-use svg::node::element::tag::Path;
 use svg::Document;
-use svg::node::element::{Circle, Definitions, Path, Style, Text, TextPath};
-use resvg::usvg::{fontdb, Options, Transform, Tree};
-use resvg::tiny_skia::{Pixmap, PixmapMut};
-use phf::phf_map;
-
-use crate::ogham::into_ogham;
-mod lambda_calculus_parser;
-
-mod ogham;
-
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let circle = Circle::new()
-        .set("cx", 500)
-        .set("cy", 500)
-        .set("r", 100)
-        .set("id", "circle1")
-        .set("fill", "none")
-        .set("stroke", "black")
-        .set("stroke-width", 1);
-
-    let circle3 = Circle::new()
-        .set("cx", 800)
-        .set("cy", 500)
-        .set("r", 105.3)
-        .set("id", "circle3")
-        .set("fill", "none")
-        .set("stroke", "black")
-        .set("stroke-width", 1.5);
-
-    let circle2 = Circle::new()
-        .set("cx", 800)
-        .set("cy", 500)
-        .set("r", 100)
-        .set("id", "circle2")
-        .set("fill", "none")
-        .set("stroke", "black")
-        .set("stroke-width", 1);
-
-
-    // let text = Text::new("!Hello, world!")
-    //     .set("x", 0)
-    //     .set("y", 600)
-    //     .set("text-anchor", "start")
-    //     .set("font-family", "Tengwar Annatar")
-    //     .set("font-size", 30)
-    //     .set("fill", "black");
-
-    let path = Path::new()
-        .set("id", "path1")
-        .set("fill", "none")
-        .set("stroke", "red")
-        .set("d", "M10,90 Q90,90 90,45 Q90,10 50,10 Q10,10 10,40 Q10,70 45,70 Q70,70 75,50");
-    
-    let text_path = TextPath::new("Hello, World, neat this is cool!")   
+use svg::node::element::{Definitions, Group, Style, Text, TextPath};
+use resvg::usvg::fontdb;
+
+use magic_circuit::ast::LambdaExpression;
+use magic_circuit::binary;
+use magic_circuit::carve::{carve, CarveOptions, Stem};
+use magic_circuit::church::recognize;
+use magic_circuit::definitions;
+use magic_circuit::evaluator::{evaluate, trace, Outcome, Strategy};
+use magic_circuit::fonts::{FontConfig, Fonts, TENGWAR_ANNATAR};
+use magic_circuit::layout::{Diagram, LayoutOptions};
+use magic_circuit::ogham;
+use magic_circuit::parse_error::ParseError;
+use magic_circuit::render::{rasterize, save_png, save_trace, Renderer};
+use magic_circuit::tromp::TrompDiagram;
+use magic_circuit::types::TypeEnvironment;
+
+use crate::cli::{Command, Drawing, EmbedFont, Input};
+
+mod cli;
+
+fn main() -> ExitCode {
+    let command = match cli::parse_args(env::args_os().skip(1).collect()) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, cli::USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match run(command) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// A term that failed to parse, displayed with the offending line.
+#[derive(Debug)]
+struct InvalidTerm {
+    source: String,
+    error: ParseError,
+}
+
+impl fmt::Display for InvalidTerm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rendered = self.error.render(&self.source);
+        write!(f, "{}", rendered.trim_start_matches("error: ").trim_end())
+    }
+}
+
+impl Error for InvalidTerm {}
+
+fn read_source(input: &Input) -> io::Result<String> {
+    match input {
+        Input::Stdin => io::read_to_string(io::stdin()),
+        Input::File(path) => fs::read_to_string(path),
+        Input::Term(term) => Ok(term.clone()),
+    }
+}
+
+/// Reads the term in `input` and the prelude with the definitions before it,
+/// which the names of the term still refer to.
+fn read_definitions(
+    input: &Input,
+) -> Result<(definitions::Definitions, LambdaExpression), Box<dyn Error>> {
+    let source = read_source(input)?;
+    let mut definitions = definitions::Definitions::prelude();
+    match definitions.load_unexpanded(&source) {
+        Ok(Some(expr)) => Ok((definitions, expr)),
+        Ok(None) => Err("expected a term after the definitions".into()),
+        Err(error) => Err(Box::new(InvalidTerm { source, error })),
+    }
+}
+
+/// Reads the term in `input`, with the prelude and the definitions before it
+/// expanded.
+fn read_term(input: &Input) -> Result<LambdaExpression, Box<dyn Error>> {
+    let (definitions, expr) = read_definitions(input)?;
+    Ok(definitions.expand(&expr))
+}
+
+/// Draws the term of `drawing`, with a `<style>` that writes its text in the
+/// configured font.
+fn draw(drawing: &Drawing, fonts: &Fonts) -> Result<Document, Box<dyn Error>> {
+    let (definitions, expr) = read_definitions(&drawing.input)?;
+    // with types the names stay free, their definitions are typed once and
+    // every use gets a fresh copy of that type
+    let expr = if drawing.types {
+        expr
+    } else {
+        definitions.expand(&expr)
+    };
+    let mut renderer = Renderer::new(&expr)
+        .style(drawing.style)
+        .bit_ring(drawing.bit_ring);
+    if drawing.types {
+        renderer = renderer.types(TypeEnvironment::from_definitions(&definitions));
+    }
+    if let Some((text, script)) = &drawing.inscription {
+        renderer = renderer
+            .inscription(text.as_str(), *script)
+            .unmappable(drawing.unmappable)
+            .carve(drawing.carve);
+    }
+    let document = renderer.to_document()?;
+    // every character of the document is a superset of those in its text
+    let characters = document.to_string();
+    let style = match drawing.embed_font {
+        EmbedFont::No => fonts.style(false, None)?,
+        EmbedFont::Full => fonts.style(true, None)?,
+        EmbedFont::Subset => fonts.style(true, Some(&characters))?,
+    };
+    Ok(match style {
+        Some(style) => document.add(style),
+        None => document,
+    })
+}
+
+fn run(command: Command) -> Result<ExitCode, Box<dyn Error>> {
+    match command {
+        Command::Help => print!("{}", cli::USAGE),
+        Command::Render(drawing) => {
+            let document = draw(&drawing, &drawing.fonts.load()?)?;
+            match &drawing.output {
+                Some(path) => svg::save(path, &document)?,
+                None => svg::write(io::stdout().lock(), &document)?,
+            }
+        }
+        Command::Png(drawing, size) => {
+            let fonts = drawing.fonts.load()?;
+            let document = draw(&drawing, &fonts)?;
+            let pixmap = rasterize(&document, size, fonts.database())?;
+            match &drawing.output {
+                Some(path) => pixmap.save_png(path)?,
+                None => io::stdout().lock().write_all(&pixmap.encode_png()?)?,
+            }
+        }
+        Command::Eval {
+            input,
+            strategy,
+            max_steps,
+        } => {
+            let expr = read_term(&input)?;
+            let evaluation = evaluate(&expr, strategy, max_steps);
+            println!("{}", evaluation.expr);
+            // written as a comment so the output can be read back as a term
+            match recognize(&evaluation.expr) {
+                Some(value) => println!(
+                    "-- {:?} after {} steps: {}",
+                    evaluation.outcome, evaluation.steps, value
+                ),
+                None => println!("-- {:?} after {} steps", evaluation.outcome, evaluation.steps),
+            }
+            if evaluation.outcome != Outcome::NormalForm {
+                return Ok(ExitCode::from(3));
+            }
+        }
+        Command::Trace {
+            input,
+            output,
+            strategy,
+            max_steps,
+        } => {
+            let expr = read_term(&input)?;
+            let fonts = FontConfig::new().load()?;
+            save_trace(&trace(&expr, strategy, max_steps), output, fonts.database())?;
+        }
+        Command::Transliterate {
+            script,
+            text,
+            unmappable,
+            reverse,
+            losses,
+        } => {
+            let text = match text {
+                Some(text) => text,
+                None => io::read_to_string(io::stdin())?,
+            };
+            for (number, line) in text.lines().enumerate() {
+                // only Ogham can be read back, see `cli::parse_args`
+                if reverse {
+                    println!("{}", ogham::from_ogham(line)?);
+                } else {
+                    println!("{}", script.transliterate_with(line, unmappable)?);
+                }
+                if losses && !reverse {
+                    for loss in ogham::losses(line) {
+                        eprintln!("line {}: {}", number + 1, loss);
+                    }
+                }
+            }
+        }
+        Command::Demo => demo()?,
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Draws the Y combinator inside the demo inscription as `image.svg` and
+/// `image.png`, next to its Tromp diagram as `comparison.svg` and
+/// `comparison.png`, and every reduction step of `PRED two` into `trace/`.
+fn demo() -> Result<(), Box<dyn Error>> {
+    let fonts = FontConfig::new().load()?;
+    let prelude = definitions::Definitions::prelude();
+    let y_combinator = prelude.parse("Y")?;
+    let diagram = Diagram::new(&y_combinator, &LayoutOptions::default());
+    let circuit = diagram
+        .to_group()
+        .add(diagram.bit_ring(&binary::encode(&y_combinator)?))
+        .set("id", "circuit")
+        .set("transform", format!("translate(1000, 1000) scale({})", 400.0 / diagram.radius));
+
+    let hello_world = "Hello, World, neat this is cool!";
+    let text_path = TextPath::new(hello_world)
         .set("x", 0)
         .set("y", 600)
         .set("href", "#circle1")
@@ -69,42 +230,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .set("fill", "black");
 
     let lorem_ipsum = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam, quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea commodo consequat. Duis aute irure dolor in reprehenderit in voluptate velit esse cillum dolore eu fugiat nulla pariatur. Excepteur sint occaecat cupidatat non proident, sunt in culpa qui officia deserunt mollit anim id est laborum.";
-    // to make sure the line connects neatly we will have to warp
-    // example: https://henry.codes/writing/how-to-distort-text-with-svg/
-    let ogham_text = TextPath::new(into_ogham(lorem_ipsum.to_string()))
-        .set("x", 0)
-        .set("y", 600)
-        // .set("href", "#test_path")
-        .set("text-anchor", "start")
-        .set("font-family", "Tengwar Annatar")
-        // .set("letter-spacing", "-4")
-        .set("font-size", 15)
-        .set("fill", "black");
 
     let text_node = Text::new("")
-        .add(ogham_text.clone())
         .add(text_path.clone());
 
-
-
-
-
-    let style = Style::new(r#"
-        @font-face {
-            font-family: "Tengwar Annatar";
-            src: url('./resources/fonts/TengwarAnnatarBoldItalic-K7r7.ttf') format("truetype");
-        }
-    "#).set("type", "text/css");
+    // inlined, so the font shows wherever image.svg is opened
+    let font_face = fonts.font_face(TENGWAR_ANNATAR, Some(hello_world))?;
+    let style = Style::new(font_face).set("type", "text/css");
 
     let defs = Definitions::new()
         .add(style);
 
-
-    let mut dots: Vec<Circle> = Vec::new();
-
     let c = 11.0;
-    let colors = ["red", "blue", "green", "yellow", "purple", "orange"];
-    // let angle: f64 = 137.508_f64.to_radians();
     let angle: f64 = (83.702_f64).to_radians();
     let mut curves = vec![vec![]; 30];
     for i in 10..300 {
@@ -113,129 +250,68 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         let x = r * theta.cos() + 300.0;
         let y = r * theta.sin() + 300.0;
         curves[i % 30].push((x, y));
-
-        // dots.push(
-        //     Circle::new()
-        //         .set("cx", x)
-        //         .set("cy", y)
-        //         .set("r", 1)
-        //         .set("fill", colors[i % colors.len()])
-        //     // .set("stroke", "black")
-        // );
     }
 
-    
-    let mut data = Data::new();
-    let mut debug_data = Data::new();
-
-    // try 3: Centripetal Catmull–Rom spline
-
-    for curve in curves.iter() {
-        data = to_catmul_rom_spline(data, curve);
+    // the Ogham is carved along the spiral arms, one after the other, so
+    // its stem lines follow them exactly
+    let options = CarveOptions::default();
+    let mut letters = &ogham::transliterate(lorem_ipsum)[..];
+    let mut carving = Group::new().set("id", "carving");
+    for curve in curves {
+        let stem = Stem::Spline(curve);
+        let (arm, carved) = carve(letters, &stem, &options)?;
+        carving = carving.add(arm);
+        letters = &letters[carved..];
     }
 
-
-    let test_path = Path::new()
-        .set("fill", "none")
-        .set("stroke", "red")
-        // .set("stroke", "none")
-        .set("id", "test_path")
-        .set("d", data);
-
-    let debug_path = Path::new()
-        .set("fill", "none")
-        .set("stroke", "blue")
-        .set("d", debug_data);
-
-
-    let mut document = Document::new()
+    let document = Document::new()
         .set("viewBox", (0, 0, 2000, 2000))
         .add(defs)
-        // .add(circle)
-        // .add(circle2)
-        // .add(circle3)
-        // .add(path)
+        .add(circuit)
         .add(text_node)
-        .add(test_path)
-        // .add(debug_path)
-        // .add(ogham_text)
-        ;
-        // .add(text_path);
-
-    for dot in dots {
-        document = document.add(dot.clone());
-    }
+        .add(carving);
 
     svg::save("image.svg", &document)?;
-    let mut svg_data = Vec::new();
-    let writer = BufWriter::new(&mut svg_data);
-    svg::write(writer, &document)?;
-    let mut fontdb = fontdb::Database::new();
-    fontdb.load_system_fonts();
-    fontdb.load_font_file("./resources/fonts/TengwarAnnatarAltBoldItalic-YzDo.ttf")?;
-    fontdb.load_font_file("./resources/fonts/TengwarAnnatarBoldItalic-K7r7.ttf")?;
-
-    let opt = Options::default();
-    let tree = Tree::from_data(&svg_data, &opt, &fontdb)?;
-    let pixmap_size = tree.size().to_int_size();
-    let mut pixmap = Pixmap::new(pixmap_size.width(), pixmap_size.height()).unwrap();
-
-    resvg::render(&tree, Transform::default(), &mut pixmap.as_mut());
-    pixmap.save_png("image.png")?;
+    let fontdb = fonts.database();
+    save_png(&document, "image.png", fontdb)?;
+    save_comparison(&y_combinator, "comparison", fontdb)?;
+
+    let pred_2 = prelude.parse("PRED two")?;
+    save_trace(&trace(&pred_2, Strategy::NormalOrder, 100), "trace", fontdb)?;
     println!("Done rendering!");
     Ok(())
 }
 
-const ALPHA: f64 = 0.5;
-fn to_catmul_rom_spline(mut data: Data, points: &[(f64, f64)]) -> Data {
-    let mut t_i = vec![0.0];
-    for i in 1..points.len() {
-        let (x1, y1) = points[i - 1];
-        let (x2, y2) = points[i];
-        let d = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt().powf(ALPHA);
-        t_i.push(t_i[i - 1] + d);
-    }
-    data = data.move_to(points[0]);
-
-    for i in 1..points.len()-2 {
-        let (x0, y0) = points[i - 1];
-        let (x1, y1) = points[i];
-        let (x2, y2) = points[i + 1];
-        let (x3, y3) = points[i + 2];
-
-        let t0 = t_i[i - 1];
-        let t1 = t_i[i];
-        let t2 = t_i[i + 1];
-        let t3 = t_i[i + 2];
-
-        let c1 = (t2 - t1) / (t2 - t0);
-        let c2 = (t1 - t0) / (t2 - t0);
-
-        let d1 = (t3 - t2) / (t3 - t1);
-        let d2 = (t2 - t1) / (t3 - t1);
-
-        let m1 = (
-            (t2 - t1) * (c1 * (x1 - x0) / (t1 - t0) + c2 * (x2 - x1) / (t2 - t1)),
-            (t2 - t1) * (c1 * (y1 - y0) / (t1 - t0) + c2 * (y2 - y1) / (t2 - t1)),
-        );
-
-        let m2 = (
-            (t2 - t1) * (d1 * (x2 - x1) / (t2 - t1) + d2 * (x3 - x2) / (t3 - t2)),
-            (t2 - t1) * (d1 * (y2 - y1) / (t2 - t1) + d2 * (y3 - y2) / (t3 - t2)),
-        );
-
-        // let q0 = (x1, x2);
-        let q1 = (
-            x1 + m1.0 / 3.0,
-            y1 + m1.1 / 3.0,
-        );
-        let q2 = (
-            x2 - m2.0 / 3.0,
-            y2 - m2.1 / 3.0,
-        );
-        let q3 = (x2, y2);
-        data = data.cubic_curve_to((q1.0, q1.1, q2.0, q2.1, q3.0, q3.1));
-    }
-
-    data
+/// Saves `path.svg` and `path.png` with the circle diagram of `expr` on the
+/// left and its Tromp diagram on the right, drawn at the same scale.
+fn save_comparison(
+    expr: &LambdaExpression,
+    path: &str,
+    fontdb: &fontdb::Database,
+) -> Result<(), Box<dyn Error>> {
+    let options = LayoutOptions::default();
+    let circles = Diagram::new(expr, &options);
+    let tromp = TrompDiagram::new(expr, &options);
+
+    let extent = circles.radius + options.margin;
+    let (width, height) = tromp.size();
+    let total_height = (2.0 * extent).max(height + 2.0 * options.margin);
+    let document = Document::new()
+        .set(
+            "viewBox",
+            (0.0, 0.0, 2.0 * extent + width + options.margin, total_height),
+        )
+        .add(
+            circles
+                .to_group()
+                .set("transform", format!("translate({}, {})", extent, total_height / 2.0)),
+        )
+        .add(tromp.to_group().set(
+            "transform",
+            format!("translate({}, {})", 2.0 * extent, (total_height - height) / 2.0),
+        ));
+
+    svg::save(format!("{}.svg", path), &document)?;
+    save_png(&document, format!("{}.png", path), fontdb)?;
+    Ok(())
 }
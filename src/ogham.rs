@@ -1,8 +1,12 @@
+use std::fmt;
 use std::iter;
+use std::ops::Range;
 
 use phf::{phf_map, Map};
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+use crate::script::{in_table, read_longest, Script};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Ogham {
     B, // Beith
     L, // Luis
@@ -40,6 +44,113 @@ pub enum Ogham {
     Space, // Space
 }
 
+impl Ogham {
+    /// Every letter in the order of the Unicode Ogham block, starting at the
+    /// space mark U+1680.
+    pub const ALL: [Ogham; 29] = [
+        Ogham::Space,
+        Ogham::B,
+        Ogham::L,
+        Ogham::F,
+        Ogham::S,
+        Ogham::N,
+        Ogham::H,
+        Ogham::D,
+        Ogham::T,
+        Ogham::C,
+        Ogham::Q,
+        Ogham::M,
+        Ogham::G,
+        Ogham::NG,
+        Ogham::Z,
+        Ogham::R,
+        Ogham::A,
+        Ogham::O,
+        Ogham::U,
+        Ogham::E,
+        Ogham::I,
+        Ogham::EA,
+        Ogham::OI,
+        Ogham::UI,
+        Ogham::IA,
+        Ogham::AE,
+        Ogham::P,
+        Ogham::Start,
+        Ogham::End,
+    ];
+
+    /// The Latin letters this letter is read as, lowercase. The feather marks
+    /// read as nothing.
+    pub fn latin(self) -> &'static str {
+        match self {
+            Ogham::B => "b",
+            Ogham::L => "l",
+            Ogham::F => "f",
+            Ogham::S => "s",
+            Ogham::N => "n",
+
+            Ogham::H => "h",
+            Ogham::D => "d",
+            Ogham::T => "t",
+            Ogham::C => "c",
+            Ogham::Q => "q",
+
+            Ogham::M => "m",
+            Ogham::G => "g",
+            Ogham::NG => "ng",
+            Ogham::Z => "z",
+            Ogham::R => "r",
+
+            Ogham::A => "a",
+            Ogham::O => "o",
+            Ogham::U => "u",
+            Ogham::E => "e",
+            Ogham::I => "i",
+
+            Ogham::EA => "ea",
+            Ogham::OI => "oi",
+            Ogham::UI => "ui",
+            Ogham::IA => "ia",
+            Ogham::AE => "ae",
+            Ogham::P => "p",
+
+            Ogham::Start | Ogham::End => "",
+            Ogham::Space => " ",
+        }
+    }
+}
+
+/// A character that is not an Ogham letter, `position` counts characters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NotOgham {
+    pub character: char,
+    pub position: usize,
+}
+
+impl fmt::Display for NotOgham {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` at {} is not an Ogham letter",
+            self.character.escape_default(),
+            self.position
+        )
+    }
+}
+
+impl std::error::Error for NotOgham {}
+
+impl TryFrom<char> for Ogham {
+    type Error = char;
+
+    fn try_from(c: char) -> Result<Self, char> {
+        Ogham::ALL
+            .into_iter()
+            .find(|&letter| char::from(letter) == c)
+            .ok_or(c)
+    }
+}
+
 impl From<Ogham> for char {
     fn from(val: Ogham) -> Self {
         match val {
@@ -71,7 +182,7 @@ impl From<Ogham> for char {
             Ogham::OI => 'ᚖ',
             Ogham::UI => 'ᚗ',
             Ogham::IA => 'ᚘ',
-            Ogham::AE => 'ᚚ',
+            Ogham::AE => 'ᚙ',
             Ogham::P => 'ᚚ',
 
             Ogham::Start => '᚛',
@@ -81,46 +192,265 @@ impl From<Ogham> for char {
     }
 }
 
-const OGHAM_MAP: Map<char, Ogham> = phf_map!{
-    'a' => Ogham::A,
-    'b' => Ogham::B,
-    'c' => Ogham::C,
-    'd' => Ogham::D,
-    'e' => Ogham::E,
-    'f' => Ogham::F,
-    'g' => Ogham::G,
-    'h' => Ogham::H,
-    'i' => Ogham::I,
-    'j' => Ogham::H,
-    'k' => Ogham::C,
-    'l' => Ogham::L,
-    'm' => Ogham::M,
-    'n' => Ogham::N,
-    'o' => Ogham::O,
-    'p' => Ogham::P,
-    'q' => Ogham::Q,
-    'r' => Ogham::R,
-    's' => Ogham::S,
-    't' => Ogham::T,
-    'u' => Ogham::U,
-    'v' => Ogham::F,
-    'w' => Ogham::EA,
-    'x' => Ogham::OI,
-    'y' => Ogham::IA,
-    'z' => Ogham::Z,
-    ' ' => Ogham::Space,
+/// Latin letters and the digraphs written with a single forfeda letter,
+/// lowercase.
+const OGHAM_MAP: Map<&'static str, Ogham> = phf_map! {
+    "a" => Ogham::A,
+    "b" => Ogham::B,
+    "c" => Ogham::C,
+    "d" => Ogham::D,
+    "e" => Ogham::E,
+    "f" => Ogham::F,
+    "g" => Ogham::G,
+    "h" => Ogham::H,
+    "i" => Ogham::I,
+    "j" => Ogham::H,
+    "k" => Ogham::C,
+    "l" => Ogham::L,
+    "m" => Ogham::M,
+    "n" => Ogham::N,
+    "o" => Ogham::O,
+    "p" => Ogham::P,
+    "q" => Ogham::Q,
+    "r" => Ogham::R,
+    "s" => Ogham::S,
+    "t" => Ogham::T,
+    "u" => Ogham::U,
+    "v" => Ogham::F,
+    "w" => Ogham::EA,
+    "x" => Ogham::OI,
+    "y" => Ogham::IA,
+    "z" => Ogham::Z,
+    " " => Ogham::Space,
+    "ng" => Ogham::NG,
+    "ea" => Ogham::EA,
+    "oi" => Ogham::OI,
+    "ui" => Ogham::UI,
+    "ia" => Ogham::IA,
+    "ae" => Ogham::AE,
 };
 
+/// The longest key of `OGHAM_MAP`, in characters.
+const LONGEST_MATCH: usize = 2;
+
+/// Every letter for `text` with the characters it was read from, the longest
+/// digraph or letter that has one is read at every position. Anything
+/// without a letter becomes a space.
+fn read_letters(text: &str) -> Vec<(Range<usize>, Ogham)> {
+    read_longest(text, &OGHAM_MAP, LONGEST_MATCH)
+        .into_iter()
+        .map(|(range, letter)| (range, letter.unwrap_or(Ogham::Space)))
+        .collect()
+}
+
+/// The Ogham letters for `text`, reading the longest digraph or letter that
+/// has one at every position. Anything without a letter becomes a space.
+pub fn transliterate(text: &str) -> Vec<Ogham> {
+    read_letters(text)
+        .into_iter()
+        .map(|(_, letter)| letter)
+        .collect()
+}
+
+/// `text` in Ogham, between the feather marks that start and end a line.
 pub fn into_ogham(text: String) -> String {
     iter::once(Ogham::Start)
-        .chain(
-            text.to_lowercase()
-                .chars()
-                .map(|c| OGHAM_MAP.get(&c).unwrap_or(&Ogham::Space))
-                .cloned()
-        )
+        .chain(transliterate(&text))
         .chain(iter::once(Ogham::End))
         .map(char::from)
         .collect()
 }
 
+/// Reads a string of Ogham codepoints, feather marks included.
+pub fn parse_ogham(text: &str) -> Result<Vec<Ogham>, NotOgham> {
+    text.chars()
+        .enumerate()
+        .map(|(position, c)| {
+            Ogham::try_from(c).map_err(|character| NotOgham {
+                character,
+                position,
+            })
+        })
+        .collect()
+}
+
+/// Reads Ogham `text` back as lowercase Latin.
+pub fn from_ogham(text: &str) -> Result<String, NotOgham> {
+    Ok(parse_ogham(text)?
+        .into_iter()
+        .map(Ogham::latin)
+        .collect())
+}
+
+/// Characters that `transliterate` writes with a letter that reads back as
+/// something else, like `k` as `c`. Case is always lost and not reported.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Loss {
+    /// index of the first character in the source, counting characters
+    pub position: usize,
+    pub source: String,
+    pub letter: Ogham,
+}
+
+impl fmt::Display for Loss {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` at {} reads back as `{}`",
+            self.source,
+            self.position,
+            self.letter.latin()
+        )
+    }
+}
+
+/// Every part of `text` that doesn't survive the trip to Ogham and back.
+pub fn losses(text: &str) -> Vec<Loss> {
+    let characters: Vec<char> = text.chars().collect();
+    read_letters(text)
+        .into_iter()
+        .filter_map(|(range, letter)| {
+            let source: String = characters[range.clone()].iter().collect();
+            (source.to_lowercase() != letter.latin()).then_some(Loss {
+                position: range.start,
+                source,
+                letter,
+            })
+        })
+        .collect()
+}
+
+/// Whether the Ogham `inscription` is `source` written with `into_ogham`,
+/// feather marks aside.
+pub fn verify(inscription: &str, source: &str) -> Result<bool, NotOgham> {
+    let is_letter = |letter: &Ogham| !matches!(letter, Ogham::Start | Ogham::End);
+    let mut inscribed = parse_ogham(inscription)?;
+    inscribed.retain(is_letter);
+    Ok(inscribed == transliterate(source))
+}
+
+/// Ogham as a `Script`, written between feather marks like `into_ogham`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OghamScript;
+
+impl Script for OghamScript {
+    fn name(&self) -> &'static str {
+        "ogham"
+    }
+
+    fn transliterate(&self, text: &str) -> String {
+        into_ogham(text.to_string())
+    }
+
+    fn can_write(&self, c: char) -> bool {
+        in_table(&OGHAM_MAP, c)
+    }
+
+    fn glyphs(&self) -> Vec<char> {
+        Ogham::ALL.into_iter().map(char::from).collect()
+    }
+
+    fn font(&self) -> &'static str {
+        "Noto Sans Ogham"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unicode_block() {
+        let block: Vec<char> = Ogham::ALL.map(char::from).to_vec();
+        let expected: Vec<char> = ('\u{1680}'..='\u{169C}').collect();
+        assert_eq!(block, expected);
+        assert_eq!(char::from(Ogham::AE), 'ᚙ');
+        assert_eq!(char::from(Ogham::P), 'ᚚ');
+    }
+
+    macro_rules! test_transliterate {
+        ($name:ident, $text:expr, $expected:expr) => {
+            ::paste::paste! {
+                #[test]
+                fn [<test_transliterate_$name>]() {
+                    assert_eq!(into_ogham($text.to_string()), $expected);
+                }
+            }
+        };
+    }
+
+    test_transliterate!(letters, "beith", "᚛ᚁᚓᚔᚈᚆ᚜");
+    test_transliterate!(ngetal, "ngetal", "᚛ᚍᚓᚈᚐᚂ᚜");
+    test_transliterate!(
+        forfeda,
+        "ea oi ui ia ae",
+        "᚛ᚕ\u{1680}ᚖ\u{1680}ᚗ\u{1680}ᚘ\u{1680}ᚙ᚜"
+    );
+    // `ae` is read first, so the `e` can't start `ea`
+    test_transliterate!(greedy, "aea", "᚛ᚙᚐ᚜");
+    test_transliterate!(uppercase, "NG", "᚛ᚍ᚜");
+    test_transliterate!(unknown, "a1", "᚛ᚐ\u{1680}᚜");
+    test_transliterate!(empty, "", "᚛᚜");
+
+    #[test]
+    fn test_round_trip() {
+        for letter in Ogham::ALL {
+            assert_eq!(Ogham::try_from(char::from(letter)), Ok(letter));
+        }
+        let text = "ngetal and the ea of eabhadh";
+        assert_eq!(from_ogham(&into_ogham(text.to_string())), Ok(text.to_string()));
+        assert!(losses(text).is_empty());
+    }
+
+    #[test]
+    fn test_from_ogham() {
+        assert_eq!(
+            parse_ogham("᚛ᚁᚍ᚜"),
+            Ok(vec![Ogham::Start, Ogham::B, Ogham::NG, Ogham::End])
+        );
+        assert_eq!(from_ogham("᚛ᚋᚐᚌᚔᚉ\u{1680}ᚙ᚜"), Ok("magic ae".to_string()));
+        assert_eq!(
+            from_ogham("ᚁx"),
+            Err(NotOgham {
+                character: 'x',
+                position: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_losses() {
+        let losses = losses("Jack, 2 vows");
+        let report: Vec<_> = losses.iter().map(Loss::to_string).collect();
+        assert_eq!(
+            report,
+            vec![
+                "`J` at 0 reads back as `h`",
+                "`k` at 3 reads back as `c`",
+                "`,` at 4 reads back as ` `",
+                "`2` at 6 reads back as ` `",
+                "`v` at 8 reads back as `f`",
+                "`w` at 10 reads back as `ea`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verify() {
+        let source = "Lorem ipsum";
+        let inscription = into_ogham(source.to_string());
+        assert_eq!(verify(&inscription, source), Ok(true));
+        assert_eq!(verify(&inscription, "Lorem ipsun"), Ok(false));
+        assert!(verify("lorem", source).is_err());
+    }
+
+    #[test]
+    fn test_every_letter_is_reachable() {
+        let reachable: Vec<Ogham> = OGHAM_MAP.values().copied().collect();
+        for letter in Ogham::ALL {
+            if letter != Ogham::Start && letter != Ogham::End {
+                assert!(reachable.contains(&letter), "{:?}", letter);
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::fmt;
+
+use nom::error::{ErrorKind, ParseError as NomParseError};
+
+/// Error produced by the nom parsers, keeps the failure that got the furthest
+/// into the input together with a message describing it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LambdaParseError<I> {
+    pub input: I,
+    pub message: Option<String>,
+}
+
+impl<I> LambdaParseError<I> {
+    pub fn new(input: I, message: impl Into<String>) -> Self {
+        LambdaParseError {
+            input,
+            message: Some(message.into()),
+        }
+    }
+
+    /// Labels the error with what was expected, unless a parser deeper down
+    /// already described it.
+    pub fn or_expected(self, expected: impl FnOnce() -> String) -> Self {
+        LambdaParseError {
+            message: self
+                .message
+                .or_else(|| Some(format!("expected {}", expected()))),
+            ..self
+        }
+    }
+}
+
+impl<'a> NomParseError<&'a str> for LambdaParseError<&'a str> {
+    fn from_error_kind(input: &'a str, _kind: ErrorKind) -> Self {
+        LambdaParseError {
+            input,
+            message: None,
+        }
+    }
+
+    fn append(_input: &'a str, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn or(self, other: Self) -> Self {
+        // the alternative that got the furthest is the most relevant one,
+        // both failed somewhere in the rest of the same input so that is the
+        // one with less of it left
+        match self.input.len().cmp(&other.input.len()) {
+            std::cmp::Ordering::Less => self,
+            std::cmp::Ordering::Greater => other,
+            std::cmp::Ordering::Equal if self.message.is_some() => self,
+            std::cmp::Ordering::Equal => other,
+        }
+    }
+}
+
+/// A parse failure located in the source text, `line` and `column` start at 1,
+/// `column` and `length` count characters.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(source: &str, offset: usize, message: impl Into<String>) -> Self {
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map(|index| index + 1).unwrap_or(0);
+        ParseError {
+            offset,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            length: source[offset..].chars().next().map_or(0, |_| 1),
+            message: message.into(),
+        }
+    }
+
+    /// Renders the error with the offending line and a caret under the span:
+    ///
+    /// ```text
+    /// error: expected `.` after binder `x`
+    ///  --> 1:5
+    ///   |
+    /// 1 | λx x
+    ///   |     ^
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        let line = source.lines().nth(self.line - 1).unwrap_or("");
+        let number = self.line.to_string();
+        let gutter = " ".repeat(number.len());
+        format!(
+            "error: {}\n{gutter}--> {}:{}\n{gutter} |\n{number} | {line}\n{gutter} | {}{}\n",
+            self.message,
+            self.line,
+            self.column,
+            " ".repeat(self.column - 1),
+            "^".repeat(self.length.max(1)),
+        )
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_and_column() {
+        let source = "λf.\n  f )";
+        let error = ParseError::new(source, source.find(')').unwrap(), "unbalanced `)`");
+        assert_eq!(error.line, 2);
+        assert_eq!(error.column, 5);
+        assert_eq!(error.length, 1);
+    }
+
+    #[test]
+    fn test_column_counts_characters() {
+        let error = ParseError::new("λx x", "λx x".len(), "expected `.` after binder `x`");
+        assert_eq!(error.column, 5);
+        assert_eq!(error.length, 0);
+    }
+
+    #[test]
+    fn test_render() {
+        let source = "λx x";
+        let error = ParseError::new(source, source.len(), "expected `.` after binder `x`");
+        assert_eq!(
+            error.render(source),
+            "error: expected `.` after binder `x`\n --> 1:5\n  |\n1 | λx x\n  |     ^\n"
+        );
+    }
+
+    #[test]
+    fn test_or_keeps_the_furthest() {
+        let source = "λx y";
+        let near = LambdaParseError::from_error_kind(source, ErrorKind::Tag);
+        let far = LambdaParseError::new(&source[3..], "expected `.`");
+        assert_eq!(near.clone().or(far.clone()), far);
+        assert_eq!(far.clone().or(near), far);
+        let described = LambdaParseError::new(source, "expected `λ`");
+        let undescribed = LambdaParseError::from_error_kind(source, ErrorKind::Tag);
+        assert_eq!(undescribed.or(described.clone()), described);
+    }
+}
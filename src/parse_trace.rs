@@ -0,0 +1,119 @@
+use std::cell::RefCell;
+use std::fmt;
+
+/// What the parser did, with the steps taken inside a construct nested under
+/// it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TraceNode {
+    pub label: String,
+    pub children: Vec<TraceNode>,
+}
+
+impl TraceNode {
+    pub fn new(label: impl Into<String>) -> Self {
+        TraceNode {
+            label: label.into(),
+            children: vec![],
+        }
+    }
+}
+
+impl fmt::Display for TraceNode {
+    /// Writes a line per node, with a pipe in front of it for every level of
+    /// nesting. Traces are as deep as the parsed term, so the tree is walked
+    /// with an explicit stack.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut stack = vec![(self, 0)];
+        while let Some((node, depth)) = stack.pop() {
+            writeln!(f, "{}{}", "|".repeat(depth), node.label)?;
+            stack.extend(node.children.iter().rev().map(|child| (child, depth + 1)));
+        }
+        Ok(())
+    }
+}
+
+/// Collects the trace of a single parse. Every parse gets its own tracer, so
+/// parses running at the same time never share any state.
+///
+/// Labels are only built when tracing is enabled, a disabled tracer costs
+/// next to nothing.
+pub struct Tracer {
+    /// the nodes that are still open, the outermost first, `None` when
+    /// tracing is disabled
+    open: Option<RefCell<Vec<TraceNode>>>,
+}
+
+impl Tracer {
+    pub fn new(enabled: bool) -> Self {
+        Tracer {
+            open: enabled.then(|| RefCell::new(vec![TraceNode::new("parse")])),
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self::new(false)
+    }
+
+    /// Records a step in the innermost open node.
+    pub fn event(&self, label: impl FnOnce() -> String) {
+        if let Some(open) = &self.open {
+            let mut open = open.borrow_mut();
+            let innermost = open.last_mut().expect("the root node is never closed");
+            innermost.children.push(TraceNode::new(label()));
+        }
+    }
+
+    /// Opens a node, the steps recorded until the matching `exit` are nested
+    /// under it.
+    pub fn enter(&self, label: impl FnOnce() -> String) {
+        if let Some(open) = &self.open {
+            open.borrow_mut().push(TraceNode::new(label()));
+        }
+    }
+
+    /// Closes the innermost node opened with `enter`.
+    pub fn exit(&self) {
+        if let Some(open) = &self.open {
+            let mut open = open.borrow_mut();
+            assert!(open.len() > 1, "`exit` without a matching `enter`");
+            let node = open.pop().expect("checked above");
+            open.last_mut().expect("checked above").children.push(node);
+        }
+    }
+
+    /// The trace, nodes that are still open because the parse failed are
+    /// closed. `None` when tracing is disabled.
+    pub fn finish(self) -> Option<TraceNode> {
+        let mut open = self.open?.into_inner();
+        while open.len() > 1 {
+            let node = open.pop().expect("checked by the loop");
+            open.last_mut().expect("checked by the loop").children.push(node);
+        }
+        open.pop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracer() {
+        let tracer = Tracer::new(true);
+        tracer.event(|| "a".to_string());
+        tracer.enter(|| "b".to_string());
+        tracer.event(|| "c".to_string());
+        tracer.exit();
+        tracer.enter(|| "d".to_string());
+        let trace = tracer.finish().unwrap();
+        assert_eq!(trace.to_string(), "parse\n|a\n|b\n||c\n|d\n");
+    }
+
+    #[test]
+    fn test_disabled_tracer() {
+        let tracer = Tracer::disabled();
+        tracer.enter(|| unreachable!("labels aren't built when disabled"));
+        tracer.exit();
+        assert_eq!(tracer.finish(), None);
+    }
+}
@@ -0,0 +1,1164 @@
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while},
+    character::complete::satisfy,
+    combinator::recognize,
+    error::ParseError as _,
+    multi::many1,
+    sequence::{pair, preceded},
+    IResult, Offset, Parser,
+};
+
+use crate::ast::LambdaExpression;
+use crate::church::numeral;
+use crate::evaluator::free_variables;
+use crate::parse_error::{LambdaParseError, ParseError};
+use crate::parse_trace::{TraceNode, Tracer};
+
+// `λ` is alphabetic, so it has to be excluded explicitly to keep `λx.x` from
+// being read as a single identifier
+fn is_identifier_start(c: char) -> bool {
+    (c.is_alphabetic() && c != 'λ') || c == '_'
+}
+
+pub(crate) fn is_identifier_continue(c: char) -> bool {
+    is_identifier_start(c) || c.is_numeric() || c == '\''
+}
+
+/// Skips a `{- -}` block comment at the start of `input`, block comments nest
+/// so code containing one can be commented out.
+fn skip_block_comment(input: &str) -> Result<&str, nom::Err<LambdaParseError<&str>>> {
+    let mut depth = 0;
+    let mut rest = input;
+    loop {
+        if let Some(after) = rest.strip_prefix("{-") {
+            depth += 1;
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("-}") {
+            depth -= 1;
+            rest = after;
+            if depth == 0 {
+                return Ok(rest);
+            }
+        } else if let Some(c) = rest.chars().next() {
+            rest = &rest[c.len_utf8()..];
+        } else {
+            return Err(nom::Err::Failure(LambdaParseError::new(
+                input,
+                "unterminated block comment",
+            )));
+        }
+    }
+}
+
+/// Skips whitespace, `--` comments up to the end of the line and `{- -}`
+/// block comments, anything that may appear between two tokens.
+pub(crate) fn whitespace(input: &str) -> IResult<&str, &str, LambdaParseError<&str>> {
+    let mut rest = input.trim_start();
+    loop {
+        if let Some(comment) = rest.strip_prefix("--") {
+            rest = &comment[comment.find('\n').unwrap_or(comment.len())..];
+        } else if rest.starts_with("{-") {
+            rest = skip_block_comment(rest)?;
+        } else {
+            return Ok((rest, &input[..input.offset(rest)]));
+        }
+        rest = rest.trim_start();
+    }
+}
+
+/// Labels errors of `parser` that aren't labeled yet with what was expected.
+pub(crate) fn expect<'a, O>(
+    expected: impl Fn() -> String,
+    mut parser: impl Parser<&'a str, O, LambdaParseError<&'a str>>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, O, LambdaParseError<&'a str>> {
+    move |input: &'a str| {
+        parser
+            .parse(input)
+            .map_err(|err| err.map(|err| err.or_expected(&expected)))
+    }
+}
+
+pub(crate) fn parse_string_variable(expr: &str) -> IResult<&str, &str, LambdaParseError<&str>> {
+    let (input, variable) =
+        recognize(pair(satisfy(is_identifier_start), take_while(is_identifier_continue)))(expr)?;
+
+    Ok((input, variable))
+}
+
+fn parse_lambda_variable(expr: &str) -> IResult<&str, LambdaExpression, LambdaParseError<&str>> {
+    let (input, variable) = parse_string_variable(expr)?;
+
+    Ok((input, LambdaExpression::Variable(variable.to_string())))
+}
+
+/// Arguments and bodies nested deeper than this are refused, the functions
+/// working on terms recurse into them and would overflow the stack.
+const MAX_NESTING: usize = 4_000;
+
+/// Chains of operands don't nest, but the functions working on terms recurse
+/// along them as well, so the whole depth is capped too.
+const MAX_TOTAL_DEPTH: usize = 8_000;
+
+/// Church numerals are nested as deep as they are large, so they are kept
+/// below `evaluator::MAX_DEPTH`. Reducing terms made of them nests them
+/// deeper still, `evaluator::evaluate` stops once that goes past it.
+const MAX_NUMERAL: usize = 1_000;
+
+/// Parses a decimal literal like `42` into the Church numeral it stands for.
+fn parse_numeral(expr: &str) -> IResult<&str, Operand, LambdaParseError<&str>> {
+    let (input, literal) = recognize(pair(
+        satisfy(|c| c.is_ascii_digit()),
+        take_while(is_identifier_continue),
+    ))(expr)?;
+    if !literal.chars().all(|c| c.is_ascii_digit()) {
+        return Err(nom::Err::Failure(LambdaParseError::new(
+            expr,
+            format!("invalid numeral `{}`", literal),
+        )));
+    }
+    let n = literal
+        .parse()
+        .ok()
+        .filter(|&n| n <= MAX_NUMERAL)
+        .ok_or_else(|| {
+            nom::Err::Failure(LambdaParseError::new(
+                expr,
+                format!("numeral `{}` is larger than {}", literal, MAX_NUMERAL),
+            ))
+        })?;
+    // `λf.λx.` and `x` around the `f`s
+    let depth = Depth {
+        nesting: n + 3,
+        total: n + 3,
+    };
+    Ok((input, (numeral(n), depth)))
+}
+
+/// Parses the `λx y z.` head of an abstraction into its binders.
+fn parse_lambda_binders(expr: &str) -> IResult<&str, Vec<&str>, LambdaParseError<&str>> {
+    let (input, lambda) = alt((tag("λ"), tag("\\")))(expr)?;
+    let (input, variables) = expect(
+        || format!("binder after `{}`", lambda),
+        many1(preceded(whitespace, parse_string_variable)),
+    )(input)?;
+    let (input, _) = expect(
+        || format!("`.` after binder `{}`", variables[variables.len() - 1]),
+        preceded(whitespace, tag(".")),
+    )(input)?;
+    Ok((input, variables))
+}
+
+/// Reads adjacent binders like `λxy.` as `λx.λy.` when the body uses some of
+/// the letters but not the whole name, so both `λxy.x y` and `λxy.xy` mean
+/// what they look like. Names with digits, primes or underscores are kept.
+fn curried_binders<'a>(binders: Vec<&'a str>, body: &LambdaExpression) -> Vec<&'a str> {
+    let splittable =
+        |binder: &str| binder.chars().count() > 1 && binder.chars().all(char::is_alphabetic);
+    if !binders.iter().any(|binder| splittable(binder)) {
+        return binders;
+    }
+    // the variables free in the body inside each binder
+    let mut free = free_variables(body);
+    let mut curried = vec![];
+    for binder in binders.into_iter().rev() {
+        let letters: Vec<&str> = binder
+            .char_indices()
+            .map(|(index, c)| &binder[index..index + c.len_utf8()])
+            .collect();
+        let split = splittable(binder)
+            && !free.contains(binder)
+            && letters.iter().any(|&letter| free.contains(letter));
+        let names = if split { letters } else { vec![binder] };
+        for name in names.into_iter().rev() {
+            free.remove(name);
+            curried.push(name);
+        }
+    }
+    curried.reverse();
+    curried
+}
+
+/// A construct that is still open while parsing, it ends at the first token
+/// that can't continue it.
+enum Frame<'a> {
+    /// `λx y z.`, the body extends as far right as possible
+    Abstraction(Vec<&'a str>),
+    /// `(`, the slice starts at the bracket
+    Parenthesized(&'a str),
+}
+
+/// How deeply a parsed term is nested.
+#[derive(Clone, Copy, Debug)]
+struct Depth {
+    /// arguments and bodies inside each other, the operands of a chain like
+    /// `f x y z` aren't nested in each other
+    nesting: usize,
+    /// the whole way down, see `LambdaExpression::depth`
+    total: usize,
+}
+
+impl Depth {
+    const VARIABLE: Depth = Depth {
+        nesting: 1,
+        total: 1,
+    };
+}
+
+/// A parsed term together with how deeply it is nested.
+type Operand = (LambdaExpression, Depth);
+
+/// Application is juxtaposition, so every operand is applied to the ones
+/// before it in the same frame, `function` is `None` for the first one.
+///
+/// Fails at `at` when the application would be nested deeper than
+/// `MAX_NESTING` or be deeper than `MAX_TOTAL_DEPTH` in all.
+fn apply(
+    at: &str,
+    function: Option<Operand>,
+    (argument, argument_depth): Operand,
+) -> Result<Operand, nom::Err<LambdaParseError<&str>>> {
+    let (expr, depth) = match function {
+        None => (argument, argument_depth),
+        Some((function, function_depth)) => (
+            LambdaExpression::Application(Box::new(function), Box::new(argument)),
+            Depth {
+                nesting: function_depth.nesting.max(argument_depth.nesting + 1),
+                total: function_depth.total.max(argument_depth.total) + 1,
+            },
+        ),
+    };
+    let message = if depth.nesting > MAX_NESTING {
+        format!("expression is nested deeper than {}", MAX_NESTING)
+    } else if depth.total > MAX_TOTAL_DEPTH {
+        format!(
+            "expression is deeper than {}, counting every operand",
+            MAX_TOTAL_DEPTH
+        )
+    } else {
+        return Ok((expr, depth));
+    };
+    Err(nom::Err::Failure(LambdaParseError::new(at, message)))
+}
+
+/// Parses an expression in a single pass, stopping at the first `)` that
+/// isn't matched or at anything else that can't continue the expression.
+///
+/// Open abstractions and brackets are kept on an explicit stack rather than
+/// being parsed recursively, so deeply nested terms can't overflow the stack.
+pub fn parse_lambda_expression(expr: &str) -> IResult<&str, LambdaExpression, LambdaParseError<&str>> {
+    parse_lambda_expression_traced(expr, &Tracer::disabled())
+}
+
+/// Like `parse_lambda_expression`, recording every token and construct in
+/// `tracer`. Brackets and abstractions are traced as nodes containing what
+/// was parsed inside them.
+///
+/// Only the parsed tokens are traced, tracing the rest of the input for every
+/// token would make parsing quadratic.
+pub fn parse_lambda_expression_traced<'a>(
+    expr: &'a str,
+    tracer: &Tracer,
+) -> IResult<&'a str, LambdaExpression, LambdaParseError<&'a str>> {
+    tracer.enter(|| "expression".to_string());
+    // every open frame, with the operands that were applied before it opened
+    let mut stack: Vec<(Frame, Option<Operand>)> = vec![];
+    // the operands applied so far in the innermost frame
+    let mut applied = None;
+    let mut input = expr;
+
+    loop {
+        (input, _) = whitespace(input)?;
+
+        if let Ok((rest, variable)) = parse_lambda_variable(input) {
+            tracer.event(|| format!("variable {}", variable));
+            applied = Some(apply(input, applied, (variable, Depth::VARIABLE))?);
+            input = rest;
+            continue;
+        }
+
+        if input.starts_with(|c: char| c.is_ascii_digit()) {
+            let (rest, numeral) = parse_numeral(input)?;
+            tracer.event(|| format!("numeral {}", &input[..input.offset(rest)]));
+            applied = Some(apply(input, applied, numeral)?);
+            input = rest;
+            continue;
+        }
+
+        if input.starts_with('(') {
+            tracer.enter(|| "bracket".to_string());
+            stack.push((Frame::Parenthesized(input), applied.take()));
+            input = &input[1..];
+            continue;
+        }
+
+        if input.starts_with(['λ', '\\']) {
+            let (rest, binders) = parse_lambda_binders(input)?;
+            tracer.enter(|| format!("abstraction λ{}", binders.join(" ")));
+            stack.push((Frame::Abstraction(binders), applied.take()));
+            input = rest;
+            continue;
+        }
+
+        // nothing can start an operand here, so the abstractions opened since
+        // the innermost bracket end here
+        while let Some((Frame::Abstraction(_), _)) = stack.last() {
+            let Some((Frame::Abstraction(binders), outer)) = stack.pop() else {
+                unreachable!("the top of the stack is an abstraction");
+            };
+            let body = applied.ok_or_else(|| {
+                nom::Err::Failure(LambdaParseError::new(input, "expected expression after `.`"))
+            })?;
+            tracer.exit();
+            let (body, depth) = body;
+            let binders = curried_binders(binders, &body);
+            let depth = Depth {
+                nesting: depth.nesting + binders.len(),
+                total: depth.total + binders.len(),
+            };
+            let abstraction = binders.into_iter().rev().fold(body, |body, variable| {
+                LambdaExpression::Abstraction(variable.to_string(), Box::new(body))
+            });
+            applied = Some(apply(input, outer, (abstraction, depth))?);
+        }
+
+        match stack.pop() {
+            None => {
+                tracer.exit();
+                return match applied {
+                    Some((expr, _)) => Ok((input, expr)),
+                    None => Err(nom::Err::Error(LambdaParseError::from_error_kind(
+                        input,
+                        nom::error::ErrorKind::IsNot,
+                    ))),
+                };
+            }
+            Some((Frame::Parenthesized(open), outer)) => {
+                if input.is_empty() {
+                    return Err(nom::Err::Failure(LambdaParseError::new(open, "unbalanced `(`")));
+                }
+                if !input.starts_with(')') {
+                    return Err(nom::Err::Failure(LambdaParseError::from_error_kind(
+                        input,
+                        nom::error::ErrorKind::Char,
+                    )));
+                }
+                let inner = applied.ok_or_else(|| {
+                    nom::Err::Failure(LambdaParseError::new(
+                        input,
+                        "expected expression inside `(` `)`",
+                    ))
+                })?;
+                tracer.exit();
+                applied = Some(apply(input, outer, inner)?);
+                input = &input[1..];
+            }
+            Some((Frame::Abstraction(_), _)) => unreachable!("abstractions are closed above"),
+        }
+    }
+}
+
+/// Describes what went wrong at `rest` when no parser said what it expected.
+pub(crate) fn unexpected(rest: &str) -> String {
+    match rest.chars().next() {
+        None => "unexpected end of input".to_string(),
+        Some(')') => "unbalanced `)`".to_string(),
+        Some(c) => format!("unexpected `{}`", c),
+    }
+}
+
+/// Parses the whole `source` as a single lambda expression.
+///
+/// With the `DEBUG_PRINT` feature the trace of the parse is printed.
+pub fn parse(source: &str) -> Result<LambdaExpression, ParseError> {
+    let tracer = Tracer::new(cfg!(feature = "DEBUG_PRINT"));
+    let result = parse_with(source, &tracer);
+    if let Some(trace) = tracer.finish() {
+        print!("{}", trace);
+    }
+    result
+}
+
+/// Like `parse`, returning the trace of the parse alongside the result.
+pub fn parse_traced(source: &str) -> (Result<LambdaExpression, ParseError>, TraceNode) {
+    let tracer = Tracer::new(true);
+    let result = parse_with(source, &tracer);
+    (result, tracer.finish().expect("tracing is enabled"))
+}
+
+fn parse_with(source: &str, tracer: &Tracer) -> Result<LambdaExpression, ParseError> {
+    let error = |rest: &str, message: Option<String>| {
+        let rest = rest.trim_start();
+        let message = message.unwrap_or_else(|| unexpected(rest));
+        ParseError::new(source, source.offset(rest), message)
+    };
+
+    match parse_lambda_expression_traced(source, tracer) {
+        Ok(("", expr)) => Ok(expr),
+        Ok((rest, _)) => Err(error(rest, None)),
+        Err(nom::Err::Error(err) | nom::Err::Failure(err)) => Err(error(err.input, err.message)),
+        Err(nom::Err::Incomplete(_)) => unreachable!("only complete parsers are used"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::{evaluate, Outcome, Strategy};
+
+    macro_rules! test_parse_lambda_expression {
+        ($name:ident, $expr:expr, $expected:expr) => {
+            ::paste::paste! {
+                #[test]
+                fn [<test_parse_lambda_expression_$name>]() {
+                    let expr = String::from($expr);
+                    let parsed_expr = parse_lambda_expression(&expr);
+                    assert_eq!(parsed_expr,
+                        Ok(
+                            (
+                                "",
+                                $expected
+                            )
+                        )
+                    );
+                }
+            }
+        };
+    }
+
+    #[test]
+    fn test_parse_lambda_application_simple() {
+        let expr = String::from("x y");
+        let parsed_expr = parse_lambda_expression(&expr);
+        assert!(parsed_expr.is_ok());
+        let (input, expr) = parsed_expr.unwrap();
+        assert_eq!(input, "");
+        assert_eq!(
+            expr,
+            LambdaExpression::Application(
+                Box::new(LambdaExpression::Variable("x".to_string())),
+                Box::new(LambdaExpression::Variable("y".to_string())),
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_lambda_variable() {
+        let expr = String::from("x");
+        let parsed_expr = parse_lambda_variable(&expr);
+        assert!(parsed_expr.is_ok());
+        let (input, expr) = parsed_expr.unwrap();
+        assert_eq!(input, "");
+        assert_eq!(expr, LambdaExpression::Variable("x".to_string()));
+    }
+
+    #[test]
+    fn test_parse_lambda_abstraction() {
+        let expr = String::from("λx.x");
+        let parsed_expr = parse_lambda_expression(&expr);
+        assert!(parsed_expr.is_ok());
+        let (input, expr) = parsed_expr.unwrap();
+        assert_eq!(input, "");
+        assert_eq!(
+            expr,
+            LambdaExpression::Abstraction(
+                "x".to_string(),
+                Box::new(LambdaExpression::Variable("x".to_string()))
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_lambda_application_complex_1() {
+        let expr = String::from("(x y)z");
+        let parsed_expr = parse_lambda_expression(&expr);
+        assert_eq!(
+            parsed_expr,
+            Ok((
+                "",
+                LambdaExpression::Application(
+                    Box::new(LambdaExpression::Application(
+                        Box::new(LambdaExpression::Variable("x".to_string())),
+                        Box::new(LambdaExpression::Variable("y".to_string())),
+                    )),
+                    Box::new(LambdaExpression::Variable("z".to_string())),
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_lambda_application_complex_2() {
+        let expr = String::from("(x y)(a b)");
+        let parsed_expr = parse_lambda_expression(&expr);
+        assert_eq!(
+            parsed_expr,
+            Ok((
+                "",
+                LambdaExpression::Application(
+                    Box::new(LambdaExpression::Application(
+                        Box::new(LambdaExpression::Variable("x".to_string())),
+                        Box::new(LambdaExpression::Variable("y".to_string())),
+                    )),
+                    Box::new(LambdaExpression::Application(
+                        Box::new(LambdaExpression::Variable("a".to_string())),
+                        Box::new(LambdaExpression::Variable("b".to_string())),
+                    )),
+                )
+            ))
+        );
+    }
+
+    test_parse_lambda_expression!(
+        true,
+        "λx.λy.x",
+        LambdaExpression::Abstraction(
+            "x".to_string(),
+            Box::new(LambdaExpression::Abstraction(
+                "y".to_string(),
+                Box::new(LambdaExpression::Variable("x".to_string()))
+            ))
+        )
+    );
+
+    test_parse_lambda_expression!(
+        false,
+        "λx.λy.y",
+        LambdaExpression::Abstraction(
+            "x".to_string(),
+            Box::new(LambdaExpression::Abstraction(
+                "y".to_string(),
+                Box::new(LambdaExpression::Variable("y".to_string()))
+            ))
+        )
+    );
+
+    test_parse_lambda_expression!(
+        s,
+        "λx.λy.λz.(x z)(y z)",
+        LambdaExpression::Abstraction(
+            "x".to_string(),
+            Box::new(LambdaExpression::Abstraction(
+                "y".to_string(),
+                Box::new(LambdaExpression::Abstraction(
+                    "z".to_string(),
+                    Box::new(LambdaExpression::Application(
+                        Box::new(LambdaExpression::Application(
+                            Box::new(LambdaExpression::Variable("x".to_string())),
+                            Box::new(LambdaExpression::Variable("z".to_string())),
+                        )),
+                        Box::new(LambdaExpression::Application(
+                            Box::new(LambdaExpression::Variable("y".to_string())),
+                            Box::new(LambdaExpression::Variable("z".to_string())),
+                        )),
+                    ))
+                ))
+            ))
+        )
+    );
+
+    test_parse_lambda_expression!(
+        optional_space_application_1,
+        "f (x x)",
+        LambdaExpression::Application(
+            Box::new(LambdaExpression::Variable("f".to_string())),
+            Box::new(LambdaExpression::Application(
+                Box::new(LambdaExpression::Variable("x".to_string())),
+                Box::new(LambdaExpression::Variable("x".to_string())),
+            ))
+        )
+    );
+
+    test_parse_lambda_expression!(
+        optional_space_application_2,
+        "f(x x)",
+        LambdaExpression::Application(
+            Box::new(LambdaExpression::Variable("f".to_string())),
+            Box::new(LambdaExpression::Application(
+                Box::new(LambdaExpression::Variable("x".to_string())),
+                Box::new(LambdaExpression::Variable("x".to_string())),
+            ))
+        )
+    );
+
+    #[test]
+    fn test_parse_stops_at_unbalanced_bracket() {
+        let expr = String::from("λx.f (x x))");
+        let (rest, parsed) = parse_lambda_expression(&expr).unwrap();
+        assert_eq!(rest, ")");
+        assert_eq!(parsed, parse_lambda_expression("λx.f (x x)").unwrap().1);
+    }
+
+    #[test]
+    fn test_parse_stops_at_unbalanced_bracket_2() {
+        let expr = String::from("x x)");
+        let (rest, parsed) = parse_lambda_expression(&expr).unwrap();
+        assert_eq!(rest, ")");
+        assert_eq!(parsed, parse_lambda_expression("x x").unwrap().1);
+    }
+
+    test_parse_lambda_expression!(
+        y_part,
+        "(λx.x x)(λx.f (x x))",
+        LambdaExpression::Application(
+            Box::new(LambdaExpression::Abstraction(
+                "x".to_string(),
+                Box::new(LambdaExpression::Application(
+                    Box::new(LambdaExpression::Variable("x".to_string())),
+                    Box::new(LambdaExpression::Variable("x".to_string())),
+                ))
+            )),
+            Box::new(LambdaExpression::Abstraction(
+                "x".to_string(),
+                Box::new(LambdaExpression::Application(
+                    Box::new(LambdaExpression::Variable("f".to_string())),
+                    Box::new(LambdaExpression::Application(
+                        Box::new(LambdaExpression::Variable("x".to_string())),
+                        Box::new(LambdaExpression::Variable("x".to_string())),
+                    )),
+                ))
+            ))
+        )
+    );
+
+    test_parse_lambda_expression!(
+        y,
+        "λf.(λx.x x)(λx.f (x x))",
+        LambdaExpression::Abstraction(
+            "f".to_string(),
+            Box::new(LambdaExpression::Application(
+                Box::new(LambdaExpression::Abstraction(
+                    "x".to_string(),
+                    Box::new(LambdaExpression::Application(
+                        Box::new(LambdaExpression::Variable("x".to_string())),
+                        Box::new(LambdaExpression::Variable("x".to_string())),
+                    ))
+                )),
+                Box::new(LambdaExpression::Abstraction(
+                    "x".to_string(),
+                    Box::new(LambdaExpression::Application(
+                        Box::new(LambdaExpression::Variable("f".to_string())),
+                        Box::new(LambdaExpression::Application(
+                            Box::new(LambdaExpression::Variable("x".to_string())),
+                            Box::new(LambdaExpression::Variable("x".to_string())),
+                        )),
+                    ))
+                ))
+            ))
+        )
+    );
+
+    test_parse_lambda_expression!(
+        church_numeral_4,
+        "λf.λx.f(f(f(f x)))",
+        LambdaExpression::Abstraction(
+            "f".to_string(),
+            Box::new(LambdaExpression::Abstraction(
+                "x".to_string(),
+                Box::new(LambdaExpression::Application(
+                    Box::new(LambdaExpression::Variable("f".to_string())),
+                    Box::new(LambdaExpression::Application(
+                        Box::new(LambdaExpression::Variable("f".to_string())),
+                        Box::new(LambdaExpression::Application(
+                            Box::new(LambdaExpression::Variable("f".to_string())),
+                            Box::new(LambdaExpression::Application(
+                                Box::new(LambdaExpression::Variable("f".to_string())),
+                                Box::new(LambdaExpression::Variable("x".to_string())),
+                            )),
+                        )),
+                    )),
+                )),
+            )),
+        )
+    );
+
+    test_parse_lambda_expression!(
+        whitespace_around_dot,
+        "λx .\tx ",
+        LambdaExpression::Abstraction(
+            "x".to_string(),
+            Box::new(LambdaExpression::Variable("x".to_string()))
+        )
+    );
+    test_parse_lambda_expression!(
+        comments,
+        "-- the identity\nλx. {- {- nested -} -} x -- returns its argument",
+        LambdaExpression::Abstraction(
+            "x".to_string(),
+            Box::new(LambdaExpression::Variable("x".to_string()))
+        )
+    );
+    test_parse_lambda_expression!(
+        comments_between_tokens,
+        "\\{-a-}f{-b-}.{-c-}f--d\n",
+        LambdaExpression::Abstraction(
+            "f".to_string(),
+            Box::new(LambdaExpression::Variable("f".to_string()))
+        )
+    );
+
+    test_parse_lambda_expression!(numeral_literal, "4", numeral(4));
+    test_parse_lambda_expression!(
+        numeral_operand,
+        "λn.n 2 (0)",
+        LambdaExpression::Abstraction(
+            "n".to_string(),
+            Box::new(LambdaExpression::Application(
+                Box::new(LambdaExpression::Application(
+                    Box::new(LambdaExpression::Variable("n".to_string())),
+                    Box::new(numeral(2)),
+                )),
+                Box::new(numeral(0)),
+            )),
+        )
+    );
+    test_parse_lambda_expression!(
+        numeral_in_identifier,
+        "x2",
+        LambdaExpression::Variable("x2".to_string())
+    );
+
+    test_parse_lambda_expression!(
+        left_associative,
+        "a b c",
+        LambdaExpression::Application(
+            Box::new(LambdaExpression::Application(
+                Box::new(LambdaExpression::Variable("a".to_string())),
+                Box::new(LambdaExpression::Variable("b".to_string())),
+            )),
+            Box::new(LambdaExpression::Variable("c".to_string())),
+        )
+    );
+
+    //λn.λf.λx.
+    // n(λg.λh.h(g f))(λu.x)(λu.u)
+    test_parse_lambda_expression!(
+        pred,
+        "λn.λf.λx.n(λg.λh.h(g f))(λu.x)(λu.u)",
+        LambdaExpression::Abstraction(
+            "n".to_string(),
+            Box::new(LambdaExpression::Abstraction(
+                "f".to_string(),
+                Box::new(LambdaExpression::Abstraction(
+                    "x".to_string(),
+                    Box::new(LambdaExpression::Application(
+                        Box::new(LambdaExpression::Application(
+                            Box::new(LambdaExpression::Application(
+                                Box::new(LambdaExpression::Variable("n".to_string())),
+                                Box::new(LambdaExpression::Abstraction(
+                                    "g".to_string(),
+                                    Box::new(LambdaExpression::Abstraction(
+                                        "h".to_string(),
+                                        Box::new(LambdaExpression::Application(
+                                            Box::new(LambdaExpression::Variable("h".to_string())),
+                                            Box::new(LambdaExpression::Application(
+                                                Box::new(LambdaExpression::Variable(
+                                                    "g".to_string()
+                                                )),
+                                                Box::new(LambdaExpression::Variable(
+                                                    "f".to_string()
+                                                )),
+                                            )),
+                                        )),
+                                    )),
+                                )),
+                            )),
+                            Box::new(LambdaExpression::Abstraction(
+                                "u".to_string(),
+                                Box::new(LambdaExpression::Variable("x".to_string())),
+                            )),
+                        )),
+                        Box::new(LambdaExpression::Abstraction(
+                            "u".to_string(),
+                            Box::new(LambdaExpression::Variable("u".to_string())),
+                        )),
+                    )),
+                )),
+            )),
+        )
+    );
+
+    test_parse_lambda_expression!(
+        function_application,
+        "f(f x)",
+        LambdaExpression::Application(
+            Box::new(LambdaExpression::Variable("f".to_string())),
+            Box::new(LambdaExpression::Application(
+                Box::new(LambdaExpression::Variable("f".to_string())),
+                Box::new(LambdaExpression::Variable("x".to_string())),
+            )),
+        )
+    );
+
+    #[test]
+    fn test_parenthesized_expression() {
+        let expr = String::from("(x y)");
+        let parsed_expr = parse_lambda_expression(&expr);
+        assert!(parsed_expr.is_ok());
+        let (input, expr) = parsed_expr.unwrap();
+        assert_eq!(input, "");
+        assert_eq!(expr, parse_lambda_expression("x y").unwrap().1);
+    }
+
+    #[test]
+    fn test_application() {
+        let expr = String::from("f(x y)");
+        let parsed_expr = parse_lambda_expression(&expr);
+        assert_eq!(
+            parsed_expr,
+            Ok((
+                "",
+                LambdaExpression::Application(
+                    Box::new(LambdaExpression::Variable("f".to_string())),
+                    Box::new(LambdaExpression::Application(
+                        Box::new(LambdaExpression::Variable("x".to_string())),
+                        Box::new(LambdaExpression::Variable("y".to_string())),
+                    )),
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_lambda_variable_multi_character() {
+        for name in ["succ", "zero", "x'", "x''", "foo_bar", "_", "n1", "pred'2"] {
+            let parsed_expr = parse_lambda_variable(name);
+            assert_eq!(
+                parsed_expr,
+                Ok(("", LambdaExpression::Variable(name.to_string())))
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_lambda_variable_must_not_start_with_digit() {
+        assert!(parse_lambda_variable("1x").is_err());
+        assert!(parse_lambda_variable("'x").is_err());
+    }
+
+    test_parse_lambda_expression!(
+        multi_character_application,
+        "succ zero",
+        LambdaExpression::Application(
+            Box::new(LambdaExpression::Variable("succ".to_string())),
+            Box::new(LambdaExpression::Variable("zero".to_string())),
+        )
+    );
+
+    test_parse_lambda_expression!(
+        multi_character_abstraction,
+        "λnum.λf'.f'(num f')",
+        LambdaExpression::Abstraction(
+            "num".to_string(),
+            Box::new(LambdaExpression::Abstraction(
+                "f'".to_string(),
+                Box::new(LambdaExpression::Application(
+                    Box::new(LambdaExpression::Variable("f'".to_string())),
+                    Box::new(LambdaExpression::Application(
+                        Box::new(LambdaExpression::Variable("num".to_string())),
+                        Box::new(LambdaExpression::Variable("f'".to_string())),
+                    )),
+                )),
+            )),
+        )
+    );
+
+    test_parse_lambda_expression!(
+        juxtaposition_needs_space,
+        "fx",
+        LambdaExpression::Variable("fx".to_string())
+    );
+
+    test_parse_lambda_expression!(
+        backslash_abstraction,
+        "\\x. x",
+        LambdaExpression::Abstraction(
+            "x".to_string(),
+            Box::new(LambdaExpression::Variable("x".to_string()))
+        )
+    );
+
+    test_parse_lambda_expression!(
+        multiple_binders,
+        "\\x y z. x z (y z)",
+        LambdaExpression::Abstraction(
+            "x".to_string(),
+            Box::new(LambdaExpression::Abstraction(
+                "y".to_string(),
+                Box::new(LambdaExpression::Abstraction(
+                    "z".to_string(),
+                    Box::new(LambdaExpression::Application(
+                        Box::new(LambdaExpression::Application(
+                            Box::new(LambdaExpression::Variable("x".to_string())),
+                            Box::new(LambdaExpression::Variable("z".to_string())),
+                        )),
+                        Box::new(LambdaExpression::Application(
+                            Box::new(LambdaExpression::Variable("y".to_string())),
+                            Box::new(LambdaExpression::Variable("z".to_string())),
+                        )),
+                    ))
+                ))
+            ))
+        )
+    );
+
+    #[test]
+    fn test_multiple_binders_desugar_to_nested_abstractions() {
+        for (curried, nested) in [
+            ("λx y.x", "λx.λy.x"),
+            ("\\f x.f(f x)", "λf.λx.f(f x)"),
+            ("λn f x.n(λg h.h(g f))(λu.x)(λu.u)", "λn.λf.λx.n(λg.λh.h(g f))(λu.x)(λu.u)"),
+        ] {
+            assert_eq!(
+                parse_lambda_expression(curried),
+                parse_lambda_expression(nested)
+            );
+        }
+    }
+
+    #[test]
+    fn test_adjacent_binders_are_curried() {
+        for (adjacent, nested) in [
+            ("λxy.x y", "λx.λy.x y"),
+            ("\\fx.f(f x)", "λf.λx.f(f x)"),
+            ("λxyz.x z (y z)", "λx.λy.λz.x z (y z)"),
+            ("λnf x.n f x", "λn.λf.λx.n f x"),
+        ] {
+            assert_eq!(parse(adjacent), parse(nested), "{}", adjacent);
+        }
+    }
+
+    // identifiers can be longer than one character, so a name the body uses
+    // as a whole stays one binder, as do names whose letters it doesn't use
+    test_parse_lambda_expression!(
+        adjacent_binders_used_whole_are_one_identifier,
+        "λxy.xy",
+        LambdaExpression::Abstraction(
+            "xy".to_string(),
+            Box::new(LambdaExpression::Variable("xy".to_string()))
+        )
+    );
+    test_parse_lambda_expression!(
+        unused_name_is_one_identifier,
+        "λzero.f",
+        LambdaExpression::Abstraction(
+            "zero".to_string(),
+            Box::new(LambdaExpression::Variable("f".to_string()))
+        )
+    );
+
+    macro_rules! test_parse_error {
+        ($name:ident, $expr:expr, $line:expr, $column:expr, $message:expr) => {
+            ::paste::paste! {
+                #[test]
+                fn [<test_parse_error_$name>]() {
+                    let error = parse($expr).unwrap_err();
+                    assert_eq!(
+                        (error.line, error.column, error.message.as_str()),
+                        ($line, $column, $message)
+                    );
+                }
+            }
+        };
+    }
+
+    #[test]
+    fn test_parse_traced() {
+        let (expr, trace) = parse_traced("λx.(x 2)");
+        assert_eq!(expr, parse("λx.(x 2)"));
+        assert_eq!(
+            trace.to_string(),
+            "parse\n|expression\n||abstraction λx\n|||bracket\n||||variable x\n||||numeral 2\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_traced_failure_keeps_the_open_nodes() {
+        let (expr, trace) = parse_traced("f (x");
+        assert!(expr.is_err());
+        assert_eq!(
+            trace.to_string(),
+            "parse\n|expression\n||variable f\n||bracket\n|||variable x\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_traced_in_parallel() {
+        let sources = ["λx.x", "f (g h)", "λa b.b a", "(λx.x x) (λx.x x)"];
+        let expected: Vec<_> = sources.iter().map(|source| parse_traced(source).1).collect();
+        let threads: Vec<_> = (0..8)
+            .map(|thread| {
+                std::thread::spawn(move || {
+                    (0..100)
+                        .map(|i| {
+                            let index = (thread + i) % sources.len();
+                            (index, parse_traced(sources[index]).1)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        for thread in threads {
+            for (index, trace) in thread.join().unwrap() {
+                assert_eq!(trace, expected[index]);
+            }
+        }
+    }
+
+    test_parse_error!(missing_dot, "λx x", 1, 5, "expected `.` after binder `x`");
+    test_parse_error!(missing_binder, "λ.x", 1, 2, "expected binder after `λ`");
+    test_parse_error!(missing_body, "\\x. ", 1, 5, "expected expression after `.`");
+    test_parse_error!(unbalanced_close, "x y)", 1, 4, "unbalanced `)`");
+    test_parse_error!(unbalanced_close_nested, "λf.f (x y))", 1, 11, "unbalanced `)`");
+    test_parse_error!(unbalanced_open, "f (x y", 1, 3, "unbalanced `(`");
+    test_parse_error!(unbalanced_open_nested, "f (x (y)", 1, 3, "unbalanced `(`");
+    test_parse_error!(unbalanced_open_innermost, "f (x (y z", 1, 6, "unbalanced `(`");
+    test_parse_error!(empty_brackets, "f ()", 1, 4, "expected expression inside `(` `)`");
+    test_parse_error!(empty, "", 1, 1, "unexpected end of input");
+    test_parse_error!(unexpected_character, "f (x ; y)", 1, 6, "unexpected `;`");
+    test_parse_error!(invalid_numeral, "f 2x", 1, 3, "invalid numeral `2x`");
+    test_parse_error!(
+        numeral_too_large,
+        "99999999999999999999999",
+        1,
+        1,
+        "numeral `99999999999999999999999` is larger than 1000"
+    );
+    test_parse_error!(
+        unterminated_block_comment,
+        "f {- x {- y -} z",
+        1,
+        3,
+        "unterminated block comment"
+    );
+    test_parse_error!(multi_line, "\n\n  f (x y", 3, 5, "unbalanced `(`");
+
+    #[test]
+    fn test_parse_error_render() {
+        let source = "λf.(λx.x x)(λx f (x x))";
+        let error = parse(source).unwrap_err();
+        assert_eq!(
+            error.render(source),
+            "error: expected `.` after binder `f`\n --> 1:18\n  |\n1 | λf.(λx.x x)(λx f (x x))\n  |                  ^\n"
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("  λx.x  "),
+            Ok(LambdaExpression::Abstraction(
+                "x".to_string(),
+                Box::new(LambdaExpression::Variable("x".to_string()))
+            ))
+        );
+    }
+
+    test_parse_lambda_expression!(
+        abstraction_extends_right,
+        "λx.x y",
+        LambdaExpression::Abstraction(
+            "x".to_string(),
+            Box::new(LambdaExpression::Application(
+                Box::new(LambdaExpression::Variable("x".to_string())),
+                Box::new(LambdaExpression::Variable("y".to_string())),
+            ))
+        )
+    );
+
+    test_parse_lambda_expression!(
+        abstraction_as_last_argument,
+        "f x λy.y x",
+        LambdaExpression::Application(
+            Box::new(LambdaExpression::Application(
+                Box::new(LambdaExpression::Variable("f".to_string())),
+                Box::new(LambdaExpression::Variable("x".to_string())),
+            )),
+            Box::new(LambdaExpression::Abstraction(
+                "y".to_string(),
+                Box::new(LambdaExpression::Application(
+                    Box::new(LambdaExpression::Variable("y".to_string())),
+                    Box::new(LambdaExpression::Variable("x".to_string())),
+                ))
+            ))
+        )
+    );
+
+    test_parse_lambda_expression!(
+        abstraction_ends_at_bracket,
+        "(λx.x) λy.y",
+        LambdaExpression::Application(
+            Box::new(LambdaExpression::Abstraction(
+                "x".to_string(),
+                Box::new(LambdaExpression::Variable("x".to_string()))
+            )),
+            Box::new(LambdaExpression::Abstraction(
+                "y".to_string(),
+                Box::new(LambdaExpression::Variable("y".to_string()))
+            ))
+        )
+    );
+
+    #[test]
+    fn test_parse_deeply_nested_brackets() {
+        let depth = 10_000;
+        let expr = format!("{}x{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(parse(&expr), Ok(LambdaExpression::Variable("x".to_string())));
+    }
+
+    #[test]
+    fn test_parse_deeply_nested_applications() {
+        let depth = 2_000;
+        let expr = format!("{}x{}", "f (".repeat(depth), ")".repeat(depth));
+        let mut parsed = &parse(&expr).unwrap();
+        for _ in 0..depth {
+            let LambdaExpression::Application(function, argument) = parsed else {
+                panic!("expected an application, got {:?}", parsed);
+            };
+            assert_eq!(**function, LambdaExpression::Variable("f".to_string()));
+            parsed = argument;
+        }
+        assert_eq!(*parsed, LambdaExpression::Variable("x".to_string()));
+    }
+
+    #[test]
+    fn test_parse_too_deeply_nested() {
+        let expr = format!("{}x{}", "f (".repeat(20_000), ")".repeat(20_000));
+        let error = parse(&expr).unwrap_err();
+        assert_eq!(error.message, "expression is nested deeper than 4000");
+    }
+
+    #[test]
+    fn test_parse_long_chain_of_operands() {
+        // the operands of a chain aren't nested in each other
+        let expr = format!("λx.{}", "x ".repeat(5_000));
+        assert!(parse(&expr).is_ok());
+        let expr = format!("λx.{}", "x ".repeat(10_000));
+        assert_eq!(
+            parse(&expr).unwrap_err().message,
+            "expression is deeper than 8000, counting every operand"
+        );
+    }
+
+    #[test]
+    fn test_evaluate_largest_numeral() {
+        let expr = parse(&format!("(λn.n) {}", MAX_NUMERAL)).unwrap();
+        let evaluation = evaluate(&expr, Strategy::NormalOrder, 10);
+        assert_eq!(evaluation.outcome, Outcome::NormalForm);
+        assert_eq!(evaluation.expr, numeral(MAX_NUMERAL));
+        assert!(evaluation.expr.to_string().starts_with("λf.λx.f (f"));
+    }
+
+    // pretty printed expressions are wrapped over several lines
+    test_parse_lambda_expression!(
+        newlines,
+        "λf.\n  f\n    x",
+        LambdaExpression::Abstraction(
+            "f".to_string(),
+            Box::new(LambdaExpression::Application(
+                Box::new(LambdaExpression::Variable("f".to_string())),
+                Box::new(LambdaExpression::Variable("x".to_string())),
+            ))
+        )
+    );
+}
@@ -0,0 +1,282 @@
+use std::fmt;
+
+use crate::ast::LambdaExpression;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PrintOptions {
+    /// print `\x.x` instead of `λx.x`
+    pub ascii: bool,
+    /// print `λx.λy.x` as `λx y.x`
+    pub collapse_binders: bool,
+    /// wrap lines that would be longer than this many characters
+    pub width: Option<usize>,
+}
+
+/// Keeps track of the current column so lines can be wrapped.
+struct Printer<'a> {
+    options: &'a PrintOptions,
+    text: String,
+    column: usize,
+}
+
+impl Printer<'_> {
+    fn push(&mut self, s: &str) {
+        self.text.push_str(s);
+        self.column += s.chars().count();
+    }
+
+    fn newline(&mut self, indent: usize) {
+        self.text.push('\n');
+        self.text.push_str(&" ".repeat(indent));
+        self.column = indent;
+    }
+
+    /// `rightmost` tells if nothing follows `expr` before the end of the
+    /// enclosing brackets, an abstraction only needs brackets when it isn't
+    /// because its body extends as far right as possible.
+    fn write(&mut self, expr: &LambdaExpression, rightmost: bool, indent: usize) {
+        if let Some(width) = self.options.width {
+            let flat = flat(expr, rightmost, self.options);
+            if self.column + flat.chars().count() <= width {
+                self.push(&flat);
+                return;
+            }
+        }
+        let wrap = self.options.width.is_some();
+
+        match expr {
+            LambdaExpression::Variable(name) => self.push(name),
+            LambdaExpression::Abstraction(_, _) => {
+                let (binders, body) = self.binders(expr);
+                let lambda = if self.options.ascii { "\\" } else { "λ" };
+                self.push(&format!("{}{}.", lambda, binders.join(" ")));
+                if wrap {
+                    self.newline(indent + 2);
+                    self.write(body, rightmost, indent + 2);
+                } else {
+                    self.write(body, rightmost, indent);
+                }
+            }
+            LambdaExpression::Application(_, _) => {
+                // application is left associative, so `f a b` is `(f a) b`
+                let mut arguments = vec![];
+                let mut head = expr;
+                while let LambdaExpression::Application(function, argument) = head {
+                    arguments.push(argument.as_ref());
+                    head = function;
+                }
+                arguments.reverse();
+
+                let parenthesize = matches!(head, LambdaExpression::Abstraction(_, _));
+                self.write_operand(head, parenthesize, false, indent);
+                for (index, argument) in arguments.iter().enumerate() {
+                    let last = index == arguments.len() - 1;
+                    let parenthesize = match argument {
+                        LambdaExpression::Variable(_) => false,
+                        LambdaExpression::Abstraction(_, _) => !(last && rightmost),
+                        LambdaExpression::Application(_, _) => true,
+                    };
+                    if wrap {
+                        self.newline(indent + 2);
+                    } else {
+                        self.push(" ");
+                    }
+                    self.write_operand(argument, parenthesize, last && rightmost, indent + 2);
+                }
+            }
+        }
+    }
+
+    fn write_operand(
+        &mut self,
+        expr: &LambdaExpression,
+        parenthesize: bool,
+        rightmost: bool,
+        indent: usize,
+    ) {
+        if parenthesize {
+            self.push("(");
+            self.write(expr, true, indent + 1);
+            self.push(")");
+        } else {
+            self.write(expr, rightmost, indent);
+        }
+    }
+
+    /// The binders printed in front of the `.` and the body after it.
+    fn binders<'e>(&self, expr: &'e LambdaExpression) -> (Vec<&'e str>, &'e LambdaExpression) {
+        let mut binders = vec![];
+        let mut body = expr;
+        while let LambdaExpression::Abstraction(binder, inner) = body {
+            binders.push(binder.as_str());
+            body = inner;
+            if !self.options.collapse_binders {
+                break;
+            }
+        }
+        (binders, body)
+    }
+}
+
+fn flat(expr: &LambdaExpression, rightmost: bool, options: &PrintOptions) -> String {
+    let options = PrintOptions {
+        width: None,
+        ..*options
+    };
+    let mut printer = Printer {
+        options: &options,
+        text: String::new(),
+        column: 0,
+    };
+    printer.write(expr, rightmost, 0);
+    printer.text
+}
+
+/// Prints `expr` in the syntax accepted by the parser, using as few brackets
+/// as possible. Parsing the result gives back `expr` as long as every name
+/// in it is a valid identifier.
+pub fn pretty_print(expr: &LambdaExpression, options: &PrintOptions) -> String {
+    let mut printer = Printer {
+        options,
+        text: String::new(),
+        column: 0,
+    };
+    printer.write(expr, true, 0);
+    printer.text
+}
+
+impl fmt::Display for LambdaExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&pretty_print(self, &PrintOptions::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    const TERMS: [&str; 12] = [
+        "x",
+        "λx.x",
+        "λx.λy.x",
+        "λx.λy.λz.(x z)(y z)",
+        "(λx.x x)(λx.f (x x))",
+        "λf.(λx.x x)(λx.f (x x))",
+        "λf.λx.f(f(f(f x)))",
+        "λn.λf.λx.n(λg.λh.h(g f))(λu.x)(λu.u)",
+        "a b c",
+        "a (b c)",
+        "f (λx.x) y λz.z",
+        "(λx.x) λsucc'.λzero.succ' zero",
+    ];
+
+    macro_rules! test_pretty_print {
+        ($name:ident, $expr:expr, $options:expr, $expected:expr) => {
+            ::paste::paste! {
+                #[test]
+                fn [<test_pretty_print_$name>]() {
+                    let expr = parse($expr).unwrap();
+                    assert_eq!(pretty_print(&expr, &$options), $expected);
+                }
+            }
+        };
+    }
+
+    test_pretty_print!(identity, "λx.x", PrintOptions::default(), "λx.x");
+    test_pretty_print!(
+        y,
+        "λf.(λx.x x)(λx.f (x x))",
+        PrintOptions::default(),
+        "λf.(λx.x x) λx.f (x x)"
+    );
+    test_pretty_print!(
+        church_numeral_4,
+        "λf.λx.f(f(f(f x)))",
+        PrintOptions::default(),
+        "λf.λx.f (f (f (f x)))"
+    );
+    test_pretty_print!(
+        pred,
+        "λn.λf.λx.n(λg.λh.h(g f))(λu.x)(λu.u)",
+        PrintOptions::default(),
+        "λn.λf.λx.n (λg.λh.h (g f)) (λu.x) λu.u"
+    );
+    test_pretty_print!(
+        left_associative,
+        "((a b) c)",
+        PrintOptions::default(),
+        "a b c"
+    );
+    test_pretty_print!(
+        abstraction_as_function,
+        "(λx.x) y",
+        PrintOptions::default(),
+        "(λx.x) y"
+    );
+    test_pretty_print!(
+        ascii,
+        "λx.λy.x",
+        PrintOptions {
+            ascii: true,
+            ..PrintOptions::default()
+        },
+        "\\x.\\y.x"
+    );
+    test_pretty_print!(
+        collapse_binders,
+        "λn.λf.λx.n(λg.λh.h(g f))(λu.x)(λu.u)",
+        PrintOptions {
+            collapse_binders: true,
+            ..PrintOptions::default()
+        },
+        "λn f x.n (λg h.h (g f)) (λu.x) λu.u"
+    );
+    test_pretty_print!(
+        wrapped,
+        "λf.(λx.f (x x))(λx.f (x x))",
+        PrintOptions {
+            width: Some(16),
+            ..PrintOptions::default()
+        },
+        "λf.\n  (λx.f (x x))\n    λx.f (x x)"
+    );
+
+    #[test]
+    fn test_display() {
+        let expr = parse("λf.(λx.x x)(λx.f (x x))").unwrap();
+        assert_eq!(expr.to_string(), "λf.(λx.x x) λx.f (x x)");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for term in TERMS {
+            let expr = parse(term).unwrap();
+            for ascii in [false, true] {
+                for collapse_binders in [false, true] {
+                    for width in [None, Some(1), Some(8), Some(20), Some(80)] {
+                        let options = PrintOptions {
+                            ascii,
+                            collapse_binders,
+                            width,
+                        };
+                        let printed = pretty_print(&expr, &options);
+                        assert_eq!(parse(&printed).as_ref(), Ok(&expr), "{:?}", printed);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_wrapped_lines_fit() {
+        let expr = parse("λf.(λx.f (x x))(λx.f (x x))").unwrap();
+        let options = PrintOptions {
+            width: Some(20),
+            ..PrintOptions::default()
+        };
+        for line in pretty_print(&expr, &options).lines() {
+            assert!(line.chars().count() <= 20, "{:?}", line);
+        }
+    }
+}
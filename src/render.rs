@@ -0,0 +1,535 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use resvg::tiny_skia::Pixmap;
+use resvg::usvg::{self, fontdb, Options, Transform, Tree};
+use svg::node::element::{Group, Text};
+use svg::Document;
+
+use crate::ast::LambdaExpression;
+use crate::binary::{encode, BinaryError};
+use crate::church::recognize;
+use crate::combinators::{self, to_combinators, Basis};
+use crate::evaluator::{Child, Trace};
+use crate::layout::{Diagram, LayoutOptions};
+use crate::ogham::OghamScript;
+use crate::script::{Script, Unmappable, UnmappableError};
+use crate::tromp::TrompDiagram;
+use crate::types::{TypeEnvironment, TypeError};
+
+/// How a term is drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Style {
+    /// nested binder circles, see `layout::Diagram`
+    #[default]
+    Circles,
+    /// horizontal binders and vertical variables, see `tromp::TrompDiagram`
+    Tromp,
+    /// the translation to combinators in the given basis, with a circle
+    /// around every application, see `combinators::to_document`
+    Combinators(Basis),
+}
+
+#[derive(Debug)]
+pub enum RenderError {
+    /// the bit ring was asked for a term with free variables
+    Encoding(BinaryError),
+    /// type annotations were asked for a term that has no type
+    Typing(TypeError),
+    /// the inscription has characters its script can't write
+    Inscription(UnmappableError),
+    /// this many letters of the carved inscription don't fit around the
+    /// drawing
+    Uncarved(usize),
+    /// resvg couldn't read the generated SVG
+    Svg(usvg::Error),
+    /// the document is too small or too large to rasterize
+    ImageSize {
+        width: u32,
+        height: u32,
+    },
+    Io(io::Error),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::Encoding(error) => write!(f, "can't draw the bit ring: {}", error),
+            RenderError::Typing(error) => write!(f, "can't annotate types: {}", error),
+            RenderError::Inscription(error) => write!(f, "can't inscribe: {}", error),
+            RenderError::Uncarved(count) => write!(
+                f,
+                "can't carve the inscription: the last {} letters don't fit around the drawing",
+                count
+            ),
+            RenderError::Svg(error) => write!(f, "invalid SVG: {}", error),
+            RenderError::ImageSize { width, height } => {
+                write!(f, "can't create a {}x{} image", width, height)
+            }
+            RenderError::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RenderError::Encoding(error) => Some(error),
+            RenderError::Typing(error) => Some(error),
+            RenderError::Inscription(error) => Some(error),
+            RenderError::Uncarved(_) => None,
+            RenderError::Svg(error) => Some(error),
+            RenderError::ImageSize { .. } => None,
+            RenderError::Io(error) => Some(error),
+        }
+    }
+}
+
+impl From<BinaryError> for RenderError {
+    fn from(error: BinaryError) -> Self {
+        RenderError::Encoding(error)
+    }
+}
+
+impl From<TypeError> for RenderError {
+    fn from(error: TypeError) -> Self {
+        RenderError::Typing(error)
+    }
+}
+
+impl From<UnmappableError> for RenderError {
+    fn from(error: UnmappableError) -> Self {
+        RenderError::Inscription(error)
+    }
+}
+
+impl From<usvg::Error> for RenderError {
+    fn from(error: usvg::Error) -> Self {
+        RenderError::Svg(error)
+    }
+}
+
+impl From<io::Error> for RenderError {
+    fn from(error: io::Error) -> Self {
+        RenderError::Io(error)
+    }
+}
+
+/// Draws a term, configured step by step:
+///
+/// ```
+/// use magic_circuit::{parser::parse, render::Renderer};
+///
+/// let expr = parse("λf.λx.f (f x)").unwrap();
+/// let document = Renderer::new(&expr).bit_ring(true).to_document().unwrap();
+/// ```
+///
+/// The bit ring, the type annotations, the highlighted redex and the
+/// inscription are only drawn in the `Style::Circles` style.
+#[derive(Clone, Debug)]
+pub struct Renderer<'a> {
+    expr: &'a LambdaExpression,
+    style: Style,
+    options: LayoutOptions,
+    bit_ring: bool,
+    types: Option<TypeEnvironment>,
+    redex: Option<Vec<Child>>,
+    inscription: Option<(String, &'static dyn Script)>,
+    inscription_id: String,
+    unmappable: Unmappable,
+    carve: bool,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(expr: &'a LambdaExpression) -> Self {
+        Renderer {
+            expr,
+            style: Style::default(),
+            options: LayoutOptions::default(),
+            bit_ring: false,
+            types: None,
+            redex: None,
+            inscription: None,
+            inscription_id: "inscription".to_string(),
+            unmappable: Unmappable::default(),
+            carve: false,
+        }
+    }
+
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn options(mut self, options: LayoutOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Draws the Binary Lambda Calculus encoding of the term around it.
+    pub fn bit_ring(mut self, enabled: bool) -> Self {
+        self.bit_ring = enabled;
+        self
+    }
+
+    /// Writes the type of every abstraction above its circle, free names are
+    /// typed with `environment`.
+    pub fn types(mut self, environment: TypeEnvironment) -> Self {
+        self.types = Some(environment);
+        self
+    }
+
+    /// Highlights the sub expression at `path`, as found by
+    /// `evaluator::step_redex`.
+    pub fn redex(mut self, path: &[Child]) -> Self {
+        self.redex = Some(path.to_vec());
+        self
+    }
+
+    /// Writes `text` in `script` around the outermost circle.
+    pub fn inscription(mut self, text: impl Into<String>, script: &'static dyn Script) -> Self {
+        self.inscription = Some((text.into(), script));
+        self
+    }
+
+    /// The id of the path the inscription is written along, `inscription` by
+    /// default. Drawings that end up in the same document need different
+    /// ones.
+    pub fn inscription_id(mut self, id: impl Into<String>) -> Self {
+        self.inscription_id = id.into();
+        self
+    }
+
+    /// What to do with the characters of the inscription its script has no
+    /// letter for, they are written as spaces by default.
+    pub fn unmappable(mut self, policy: Unmappable) -> Self {
+        self.unmappable = policy;
+        self
+    }
+
+    /// Carves an Ogham inscription as strokes, see `carve::carve`, instead
+    /// of writing it in a font, which fails when it doesn't fit around the
+    /// drawing. Other scripts are still written.
+    pub fn carve(mut self, enabled: bool) -> Self {
+        self.carve = enabled;
+        self
+    }
+
+    fn diagram(&self) -> Result<Diagram, RenderError> {
+        let mut diagram = match &self.redex {
+            Some(path) => Diagram::with_redex(self.expr, &self.options, path),
+            None => Diagram::new(self.expr, &self.options),
+        };
+        if let Some(environment) = &self.types {
+            diagram.annotate(environment.abstraction_types(self.expr)?);
+        }
+        Ok(diagram)
+    }
+
+    fn draw_inscription(&self, diagram: &Diagram) -> Result<Option<Group>, RenderError> {
+        let Some((text, script)) = &self.inscription else {
+            return Ok(None);
+        };
+        let inscription = if self.carve && script.name() == OghamScript.name() {
+            match diagram.carved_inscription(text, self.unmappable)? {
+                (carving, 0) => carving,
+                (_, left_out) => return Err(RenderError::Uncarved(left_out)),
+            }
+        } else {
+            diagram.inscription(&self.inscription_id, text, *script, self.unmappable)?
+        };
+        Ok(Some(inscription))
+    }
+
+    /// The drawing, centered on the origin for `Style::Circles` and
+    /// `Style::Combinators` and with its top left corner there for
+    /// `Style::Tromp`.
+    pub fn to_group(&self) -> Result<Group, RenderError> {
+        match self.style {
+            Style::Circles => {
+                let diagram = self.diagram()?;
+                let mut group = diagram.to_group();
+                if self.bit_ring {
+                    group = group.add(diagram.bit_ring(&encode(self.expr)?));
+                }
+                if let Some(inscription) = self.draw_inscription(&diagram)? {
+                    group = group.add(inscription);
+                }
+                Ok(group)
+            }
+            Style::Tromp => Ok(TrompDiagram::new(self.expr, &self.options).to_group()),
+            Style::Combinators(basis) => Ok(combinators::to_group(
+                &to_combinators(self.expr, basis),
+                &self.options,
+            )),
+        }
+    }
+
+    /// A standalone document whose viewBox fits the whole drawing.
+    pub fn to_document(&self) -> Result<Document, RenderError> {
+        match self.style {
+            Style::Circles => {
+                let diagram = self.diagram()?;
+                let mut document = diagram.to_document();
+                if self.bit_ring {
+                    document = document.add(diagram.bit_ring(&encode(self.expr)?));
+                }
+                if let Some(inscription) = self.draw_inscription(&diagram)? {
+                    document = document.add(inscription);
+                }
+                Ok(document)
+            }
+            Style::Tromp => Ok(TrompDiagram::new(self.expr, &self.options).to_document()),
+            Style::Combinators(basis) => Ok(combinators::to_document(
+                &to_combinators(self.expr, basis),
+                &self.options,
+            )),
+        }
+    }
+
+    pub fn save_svg(&self, path: impl AsRef<Path>) -> Result<(), RenderError> {
+        svg::save(path, &self.to_document()?)?;
+        Ok(())
+    }
+
+    pub fn save_png(
+        &self,
+        path: impl AsRef<Path>,
+        fontdb: &fontdb::Database,
+    ) -> Result<(), RenderError> {
+        save_png(&self.to_document()?, path, fontdb)
+    }
+}
+
+/// How large a rasterized image is.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ImageSize {
+    /// one pixel per unit of the viewBox
+    #[default]
+    Natural,
+    /// scaled to this many pixels wide, keeping the aspect ratio
+    Width(u32),
+    /// scaled by this factor
+    Scale(f32),
+}
+
+/// Rasterizes `document` at the size of its viewBox, text is drawn with the
+/// fonts in `fontdb`.
+pub fn to_png(document: &Document, fontdb: &fontdb::Database) -> Result<Pixmap, RenderError> {
+    rasterize(document, ImageSize::Natural, fontdb)
+}
+
+/// Like `to_png`, scaled to `size`.
+pub fn rasterize(
+    document: &Document,
+    size: ImageSize,
+    fontdb: &fontdb::Database,
+) -> Result<Pixmap, RenderError> {
+    let tree = Tree::from_str(&document.to_string(), &Options::default(), fontdb)?;
+    let natural = tree.size();
+    let scale = match size {
+        ImageSize::Natural => 1.0,
+        ImageSize::Width(width) => width as f32 / natural.width(),
+        ImageSize::Scale(scale) => scale,
+    };
+    let (width, height) = (
+        (natural.width() * scale).ceil() as u32,
+        (natural.height() * scale).ceil() as u32,
+    );
+    let mut pixmap = Pixmap::new(width, height).ok_or(RenderError::ImageSize { width, height })?;
+    resvg::render(
+        &tree,
+        Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+    Ok(pixmap)
+}
+
+pub fn save_png(
+    document: &Document,
+    path: impl AsRef<Path>,
+    fontdb: &fontdb::Database,
+) -> Result<(), RenderError> {
+    to_png(document, fontdb)?
+        .save_png(path)
+        .map_err(|error| RenderError::Io(io::Error::other(error)))
+}
+
+/// A document for every term of `trace`, with the redex contracted next
+/// highlighted and the substitution it performs written above it. The last
+/// one says how the reduction ended instead.
+pub fn trace_frames(trace: &Trace, options: &LayoutOptions) -> Vec<Document> {
+    let terms = std::iter::once(&trace.initial).chain(trace.steps.iter().map(|step| &step.expr));
+    let redexes = trace
+        .steps
+        .iter()
+        .map(|step| Some(&step.redex))
+        .chain(std::iter::once(None));
+    terms
+        .zip(redexes)
+        .enumerate()
+        .map(|(index, (term, redex))| {
+            let (diagram, caption) = match redex {
+                Some(redex) => (
+                    Diagram::with_redex(term, options, &redex.path),
+                    format!("{}: {}", index, redex),
+                ),
+                None => {
+                    let caption = match recognize(term) {
+                        Some(value) => format!("{}: {:?}, {}", index, trace.outcome, value),
+                        None => format!("{}: {:?}", index, trace.outcome),
+                    };
+                    (Diagram::new(term, options), caption)
+                }
+            };
+            let extent = diagram.radius + options.margin;
+            diagram.to_document().add(
+                Text::new(caption)
+                    .set("x", -extent + 4.0)
+                    .set("y", -extent + 12.0)
+                    .set("font-size", 8)
+                    .set("fill", "black"),
+            )
+        })
+        .collect()
+}
+
+/// Saves the `trace_frames` of `trace` as `frame_000.svg`, `frame_000.png`,
+/// ... in `directory`, which is created if it doesn't exist.
+pub fn save_trace(
+    trace: &Trace,
+    directory: impl AsRef<Path>,
+    fontdb: &fontdb::Database,
+) -> Result<(), RenderError> {
+    let directory = directory.as_ref();
+    fs::create_dir_all(directory)?;
+    for (index, document) in trace_frames(trace, &LayoutOptions::default())
+        .iter()
+        .enumerate()
+    {
+        let path = directory.join(format!("frame_{:03}", index));
+        svg::save(path.with_extension("svg"), document)?;
+        save_png(document, path.with_extension("png"), fontdb)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::{trace, Strategy};
+    use crate::parser::parse;
+    use crate::tengwar::Tengwar;
+
+    #[test]
+    fn test_styles() {
+        let expr = parse("λx.x x").unwrap();
+        let circles = Renderer::new(&expr).to_group().unwrap().to_string();
+        let tromp = Renderer::new(&expr)
+            .style(Style::Tromp)
+            .to_group()
+            .unwrap()
+            .to_string();
+        assert!(circles.contains("<circle"));
+        assert!(!tromp.contains("<circle"));
+        assert!(tromp.contains("<line"));
+
+        // λx.x x is S I I, two applications around three combinators
+        let combinators = Renderer::new(&expr)
+            .style(Style::Combinators(Basis::Ski))
+            .to_group()
+            .unwrap()
+            .to_string();
+        assert_eq!(combinators.matches("<circle").count(), 5);
+        assert!(combinators.contains("\nS\n</text>"));
+        assert!(combinators.contains("\nI\n</text>"));
+
+        let inscribed = Renderer::new(&expr)
+            .inscription("omega", &Tengwar)
+            .to_document()
+            .unwrap()
+            .to_string();
+        assert!(inscribed.contains("font-family: &apos;Tengwar Annatar&apos;"));
+        assert!(inscribed.contains(">tYxR`E</textPath>"));
+        assert!(inscribed.contains("href=\"#inscription\""));
+        let second = Renderer::new(&expr)
+            .inscription("omega", &Tengwar)
+            .inscription_id("second")
+            .to_group()
+            .unwrap()
+            .to_string();
+        assert!(second.contains("id=\"second\""));
+        assert!(second.contains("href=\"#second\""));
+        let carved = Renderer::new(&expr)
+            .inscription("omega", &OghamScript)
+            .carve(true)
+            .to_group()
+            .unwrap()
+            .to_string();
+        assert!(!carved.contains("<text"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let expr = parse("λx.y").unwrap();
+        assert!(matches!(
+            Renderer::new(&expr).bit_ring(true).to_document(),
+            Err(RenderError::Encoding(BinaryError::FreeVariable(_)))
+        ));
+        assert!(Renderer::new(&expr)
+            .inscription("y", &Tengwar)
+            .unmappable(Unmappable::Error)
+            .to_group()
+            .is_ok());
+        assert!(matches!(
+            Renderer::new(&expr)
+                .inscription("y = 1", &Tengwar)
+                .unmappable(Unmappable::Error)
+                .to_group(),
+            Err(RenderError::Inscription(_))
+        ));
+        assert!(matches!(
+            Renderer::new(&expr)
+                .inscription("y".repeat(100), &OghamScript)
+                .carve(true)
+                .to_group(),
+            Err(RenderError::Uncarved(_))
+        ));
+        let omega = parse("λx.x x").unwrap();
+        assert!(matches!(
+            Renderer::new(&omega)
+                .types(TypeEnvironment::new())
+                .to_document(),
+            Err(RenderError::Typing(_))
+        ));
+    }
+
+    #[test]
+    fn test_to_png() {
+        let expr = parse("λx.x").unwrap();
+        let document = Renderer::new(&expr).to_document().unwrap();
+        let pixmap = to_png(&document, &fontdb::Database::new()).unwrap();
+        assert!(pixmap.width() > 0 && pixmap.height() > 0);
+
+        let wide = rasterize(&document, ImageSize::Width(500), &fontdb::Database::new()).unwrap();
+        assert_eq!(wide.width(), 500);
+        assert_eq!(wide.height(), 500);
+        let double = rasterize(&document, ImageSize::Scale(2.0), &fontdb::Database::new()).unwrap();
+        assert_eq!(double.width(), 2 * pixmap.width());
+    }
+
+    #[test]
+    fn test_trace_frames() {
+        let trace = trace(&parse("(λx.x) y").unwrap(), Strategy::NormalOrder, 10);
+        let frames: Vec<String> = trace_frames(&trace, &LayoutOptions::default())
+            .iter()
+            .map(Document::to_string)
+            .collect();
+        assert_eq!(frames.len(), 2);
+        assert!(frames[0].contains(LayoutOptions::default().highlight));
+        assert!(frames[0].contains("0: [x := y]"));
+        assert!(frames[1].contains("1: NormalForm"));
+    }
+}
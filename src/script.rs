@@ -0,0 +1,347 @@
+use std::fmt;
+use std::ops::Range;
+
+use phf::{phf_map, Map};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::futhark::ElderFuthark;
+use crate::ogham::OghamScript;
+use crate::tengwar::Tengwar;
+use crate::theban::Theban;
+
+/// What `Script::transliterate_with` does with the characters a script has
+/// no letter for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Unmappable {
+    /// they are left out
+    Drop,
+    /// each one is written as a space, like `Script::transliterate` does
+    #[default]
+    Space,
+    /// digits are spelled out as English words, anything else is a space
+    SpellDigits,
+    /// letters are written without their accents, `ß`, `æ`, `ø`, `þ` and the
+    /// like as the Latin letters they stand for, anything else is a space
+    StripDiacritics,
+    /// nothing is written, the error lists them all
+    Error,
+}
+
+/// The characters of a text a script has no letter for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnmappableError {
+    /// the name of the script
+    pub script: &'static str,
+    /// every such character with its position, counted in characters
+    pub characters: Vec<(usize, char)>,
+}
+
+impl fmt::Display for UnmappableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let characters: Vec<String> = self
+            .characters
+            .iter()
+            .map(|(position, c)| format!("{:?} at {}", c, position))
+            .collect();
+        write!(
+            f,
+            "`{}` has no letter for {}",
+            self.script,
+            characters.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for UnmappableError {}
+
+const DIGITS: [&str; 10] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+
+/// Lowercase letters that have no decomposition but are written with plain
+/// Latin ones.
+const FOLDED: Map<char, &'static str> = phf_map! {
+    'ß' => "ss",
+    'æ' => "ae",
+    'œ' => "oe",
+    'ø' => "o",
+    'þ' => "th",
+    'ð' => "dh",
+    'ł' => "l",
+    'đ' => "d",
+    'ı' => "i",
+};
+
+impl Unmappable {
+    /// `text` with the characters `can_write` is false for replaced as the
+    /// policy says, everything else is left as it is.
+    fn apply(
+        self,
+        text: &str,
+        script: &'static str,
+        can_write: impl Fn(char) -> bool,
+    ) -> Result<String, UnmappableError> {
+        let mut written = String::new();
+        let mut after_digit = false;
+        for c in text.chars() {
+            let writable = can_write(c);
+            // spelled digits are words of their own
+            if self == Unmappable::SpellDigits && after_digit && writable && c != ' ' {
+                written.push(' ');
+            }
+            after_digit = false;
+            if writable {
+                written.push(c);
+                continue;
+            }
+            match self {
+                Unmappable::Drop => {}
+                Unmappable::Space | Unmappable::Error => written.push(' '),
+                Unmappable::SpellDigits => match c.to_digit(10) {
+                    Some(digit) => {
+                        if !written.is_empty() && !written.ends_with(' ') {
+                            written.push(' ');
+                        }
+                        written.push_str(DIGITS[digit as usize]);
+                        after_digit = true;
+                    }
+                    None => written.push(' '),
+                },
+                Unmappable::StripDiacritics => {
+                    let lower = lowercase(c);
+                    let stripped: String = match FOLDED.get(&lower) {
+                        Some(folded) => folded.to_string(),
+                        None => lower.nfd().filter(|&c| !is_combining_mark(c)).collect(),
+                    };
+                    if !stripped.is_empty() && stripped.chars().all(&can_write) {
+                        written.push_str(&stripped);
+                    } else {
+                        written.push(' ');
+                    }
+                }
+            }
+        }
+
+        if self == Unmappable::Error {
+            let characters: Vec<(usize, char)> = text
+                .chars()
+                .enumerate()
+                .filter(|&(_, c)| !can_write(c))
+                .collect();
+            if !characters.is_empty() {
+                return Err(UnmappableError { script, characters });
+            }
+        }
+        Ok(written)
+    }
+}
+
+/// A writing system Latin text can be transliterated into.
+pub trait Script: fmt::Debug + Sync {
+    /// Short lowercase name, as found by `by_name`.
+    fn name(&self) -> &'static str;
+
+    /// Characters without a letter are written as spaces.
+    fn transliterate(&self, text: &str) -> String;
+
+    /// Whether `transliterate` has a letter for `c`, on its own or as part of
+    /// a digraph. Case doesn't matter.
+    fn can_write(&self, c: char) -> bool;
+
+    /// Like `transliterate`, with the characters it has no letter for handled
+    /// by `policy`.
+    fn transliterate_with(
+        &self,
+        text: &str,
+        policy: Unmappable,
+    ) -> Result<String, UnmappableError> {
+        let text = policy.apply(text, self.name(), |c| self.can_write(c))?;
+        Ok(self.transliterate(&text))
+    }
+
+    /// Every character `transliterate` can write.
+    fn glyphs(&self) -> Vec<char>;
+
+    /// The font family the transliterated text has to be drawn in.
+    fn font(&self) -> &'static str;
+}
+
+impl PartialEq for dyn Script + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        self.name() == other.name()
+    }
+}
+
+/// Every script, by name.
+pub const SCRIPTS: [&dyn Script; 4] = [&OghamScript, &Tengwar, &ElderFuthark, &Theban];
+
+pub fn by_name(name: &str) -> Option<&'static dyn Script> {
+    SCRIPTS.into_iter().find(|script| script.name() == name)
+}
+
+/// `c` in lowercase, if that is a single character. Characters are lowercased
+/// one by one so positions in a text stay the same, the few whose lowercase
+/// is longer are in no table anyway.
+fn lowercase(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(lower), None) => lower,
+        _ => c,
+    }
+}
+
+/// Whether `c` is a key of `table` in lowercase.
+pub(crate) fn in_table<T>(table: &Map<&'static str, T>, c: char) -> bool {
+    table.contains_key(lowercase(c).encode_utf8(&mut [0; 4]))
+}
+
+/// Reads `text` with the longest key of `table` at every position, keys are
+/// lowercase and at most `longest` characters long. Characters no key starts
+/// with are read one by one as `None`. Ranges count characters of `text`.
+pub(crate) fn read_longest<T: Copy>(
+    text: &str,
+    table: &Map<&'static str, T>,
+    longest: usize,
+) -> Vec<(Range<usize>, Option<T>)> {
+    let text: Vec<char> = text.chars().map(lowercase).collect();
+    let mut read = vec![];
+    let mut position = 0;
+    while position < text.len() {
+        let longest = longest.min(text.len() - position);
+        let (length, value) = (1..=longest)
+            .rev()
+            .find_map(|length| {
+                let key: String = text[position..position + length].iter().collect();
+                table.get(key.as_str()).map(|&value| (length, Some(value)))
+            })
+            .unwrap_or((1, None));
+        read.push((position..position + length, value));
+        position += length;
+    }
+    read
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_by_name() {
+        for script in SCRIPTS {
+            assert_eq!(by_name(script.name()), Some(script));
+        }
+        assert_eq!(by_name("latin"), None);
+    }
+
+    #[test]
+    fn test_glyphs_cover_transliteration() {
+        let text = "The quick brown fox jumps over the lazy dog";
+        for script in SCRIPTS {
+            let glyphs = script.glyphs();
+            for c in script.transliterate(text).chars() {
+                assert!(glyphs.contains(&c), "{} writes {:?}", script.name(), c);
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    struct Latin;
+
+    impl Script for Latin {
+        fn name(&self) -> &'static str {
+            "latin"
+        }
+
+        fn transliterate(&self, text: &str) -> String {
+            text.to_string()
+        }
+
+        fn can_write(&self, c: char) -> bool {
+            c.is_ascii_alphabetic() || c == ' '
+        }
+
+        fn glyphs(&self) -> Vec<char> {
+            vec![]
+        }
+
+        fn font(&self) -> &'static str {
+            "serif"
+        }
+    }
+
+    macro_rules! test_unmappable {
+        ($name:ident, $policy:ident, $text:expr, $expected:expr) => {
+            ::paste::paste! {
+                #[test]
+                fn [<test_unmappable_$name>]() {
+                    let written: Result<String, UnmappableError> = $expected.map(str::to_string);
+                    assert_eq!(Latin.transliterate_with($text, Unmappable::$policy), written);
+                }
+            }
+        };
+    }
+
+    test_unmappable!(drop, Drop, "Café No. 5!", Ok("Caf No "));
+    test_unmappable!(space, Space, "Café No. 5!", Ok("Caf  No    "));
+    test_unmappable!(
+        digits,
+        SpellDigits,
+        "route 66, 1st",
+        Ok("route six six  one st")
+    );
+    test_unmappable!(digits_alone, SpellDigits, "42", Ok("four two"));
+    test_unmappable!(
+        diacritics,
+        StripDiacritics,
+        "Crème Brûlée, Æsir",
+        Ok("Creme Brulee  aesir")
+    );
+    test_unmappable!(
+        diacritics_unknown,
+        StripDiacritics,
+        "Ωmega ñ",
+        Ok(" mega n")
+    );
+    test_unmappable!(
+        error,
+        Error,
+        "Café No. 5!",
+        Err(UnmappableError {
+            script: "latin",
+            characters: vec![(3, 'é'), (7, '.'), (9, '5'), (10, '!')],
+        })
+    );
+
+    #[test]
+    fn test_unmappable_error_message() {
+        let error = Latin
+            .transliterate_with("a1!", Unmappable::Error)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`latin` has no letter for '1' at 1, '!' at 2"
+        );
+    }
+
+    #[test]
+    fn test_every_script_applies_the_policy() {
+        for script in SCRIPTS {
+            assert_eq!(
+                script.transliterate_with("Café 3", Unmappable::Space),
+                Ok(script.transliterate("Café 3"))
+            );
+            assert_eq!(
+                script.transliterate_with("Café 3", Unmappable::StripDiacritics),
+                Ok(script.transliterate("Cafe  "))
+            );
+            assert_eq!(
+                script.transliterate_with("Café 3", Unmappable::SpellDigits),
+                Ok(script.transliterate("Caf  three"))
+            );
+            assert!(script
+                .transliterate_with("Café", Unmappable::Error)
+                .is_err());
+        }
+    }
+}
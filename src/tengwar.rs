@@ -0,0 +1,144 @@
+use phf::{phf_map, Map};
+
+use crate::fonts::TENGWAR_ANNATAR;
+use crate::script::{in_table, read_longest, Script};
+
+/// What a Latin letter or digraph is written with, in the keyboard encoding of
+/// Dan Smith's Tengwar fonts, which Tengwar Annatar uses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+    /// one or more tengwar for a consonant
+    Tengwa(&'static str),
+    /// the tehta for a vowel, drawn over the next tengwa
+    Tehta(char),
+    /// spaces and punctuation
+    Mark(&'static str),
+}
+
+/// The short carrier, which holds a tehta that has no consonant after it.
+const CARRIER: char = '`';
+
+/// Lowercase Latin for the English tehta mode, every vowel is written over the
+/// consonant after it.
+const ENGLISH: Map<&'static str, Token> = phf_map! {
+    "t" => Token::Tengwa("1"),
+    "p" => Token::Tengwa("q"),
+    "k" => Token::Tengwa("z"),
+    "c" => Token::Tengwa("z"),
+    "q" => Token::Tengwa("z"),
+    "ck" => Token::Tengwa("z"),
+    "ch" => Token::Tengwa("a"),
+    "d" => Token::Tengwa("2"),
+    "b" => Token::Tengwa("w"),
+    "j" => Token::Tengwa("s"),
+    "g" => Token::Tengwa("x"),
+    "th" => Token::Tengwa("3"),
+    "dh" => Token::Tengwa("4"),
+    "f" => Token::Tengwa("e"),
+    "ph" => Token::Tengwa("e"),
+    "v" => Token::Tengwa("r"),
+    "sh" => Token::Tengwa("d"),
+    "zh" => Token::Tengwa("f"),
+    "wh" => Token::Tengwa("o"),
+    "n" => Token::Tengwa("5"),
+    "m" => Token::Tengwa("t"),
+    "ng" => Token::Tengwa("b"),
+    "r" => Token::Tengwa("7"),
+    "w" => Token::Tengwa("y"),
+    "y" => Token::Tengwa("h"),
+    "l" => Token::Tengwa("j"),
+    "s" => Token::Tengwa("8"),
+    "z" => Token::Tengwa("k"),
+    "h" => Token::Tengwa("9"),
+    "x" => Token::Tengwa("z8"),
+    "a" => Token::Tehta('E'),
+    "e" => Token::Tehta('R'),
+    "i" => Token::Tehta('T'),
+    "o" => Token::Tehta('Y'),
+    "u" => Token::Tehta('U'),
+    " " => Token::Mark(" "),
+    "," => Token::Mark("="),
+    "." => Token::Mark("-"),
+};
+
+/// The Tengwar of Fëanor in the English tehta mode, for the bundled Tengwar
+/// Annatar fonts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tengwar;
+
+impl Script for Tengwar {
+    fn name(&self) -> &'static str {
+        "tengwar"
+    }
+
+    /// Digraphs are read before single letters, anything else is written as
+    /// a space.
+    fn transliterate(&self, text: &str) -> String {
+        let tokens: Vec<Token> = read_longest(text, &ENGLISH, 2)
+            .into_iter()
+            .map(|(_, token)| token.unwrap_or(Token::Mark(" ")))
+            .collect();
+        let mut tengwar = String::new();
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some(token) = tokens.next() {
+            match token {
+                Token::Tengwa(tengwa) | Token::Mark(tengwa) => tengwar.push_str(tengwa),
+                // the tehta goes over the first tengwa of the consonant
+                Token::Tehta(tehta) => {
+                    match tokens.next_if(|next| matches!(next, Token::Tengwa(_))) {
+                        Some(Token::Tengwa(tengwa)) => {
+                            let mut glyphs = tengwa.chars();
+                            tengwar.extend(glyphs.next());
+                            tengwar.push(tehta);
+                            tengwar.extend(glyphs);
+                        }
+                        _ => {
+                            tengwar.push(CARRIER);
+                            tengwar.push(tehta);
+                        }
+                    }
+                }
+            }
+        }
+        tengwar
+    }
+
+    fn can_write(&self, c: char) -> bool {
+        in_table(&ENGLISH, c)
+    }
+
+    fn glyphs(&self) -> Vec<char> {
+        let mut glyphs: Vec<char> = ENGLISH
+            .values()
+            .flat_map(|token| match token {
+                Token::Tengwa(glyphs) | Token::Mark(glyphs) => glyphs.chars().collect(),
+                Token::Tehta(tehta) => vec![*tehta],
+            })
+            .chain([CARRIER])
+            .collect();
+        glyphs.sort();
+        glyphs.dedup();
+        glyphs
+    }
+
+    fn font(&self) -> &'static str {
+        TENGWAR_ANNATAR
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transliterate() {
+        // the tehta goes on the consonant after its vowel
+        assert_eq!(Tengwar.transliterate("at"), "1E");
+        assert_eq!(Tengwar.transliterate("Magic"), "txEzT");
+        // a vowel at the end of a word or before another one has a carrier
+        assert_eq!(Tengwar.transliterate("the idea"), "3`R 2T`R`E");
+        assert_eq!(Tengwar.transliterate("shing, x."), "dbT= z8-");
+        // `x` is written with two tengwar, the tehta goes over the first
+        assert_eq!(Tengwar.transliterate("fox"), "ezY8");
+    }
+}
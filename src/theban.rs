@@ -0,0 +1,85 @@
+use phf::{phf_map, Map};
+
+use crate::script::{in_table, read_longest, Script};
+
+/// The Latin letters Theban fonts draw their glyphs on, for lowercase Latin.
+/// Theban has no letters of its own for J, U and W, they are written with
+/// those for I and V.
+const THEBAN: Map<&'static str, &'static str> = phf_map! {
+    "a" => "A",
+    "b" => "B",
+    "c" => "C",
+    "d" => "D",
+    "e" => "E",
+    "f" => "F",
+    "g" => "G",
+    "h" => "H",
+    "i" => "I",
+    "j" => "I",
+    "k" => "K",
+    "l" => "L",
+    "m" => "M",
+    "n" => "N",
+    "o" => "O",
+    "p" => "P",
+    "q" => "Q",
+    "r" => "R",
+    "s" => "S",
+    "t" => "T",
+    "u" => "V",
+    "v" => "V",
+    "w" => "VV",
+    "x" => "X",
+    "y" => "Y",
+    "z" => "Z",
+    " " => " ",
+    "." => ".",
+};
+
+/// The Theban alphabet. Unicode has no block for it, so it is written as the
+/// Latin letters a Theban font draws it on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Theban;
+
+impl Script for Theban {
+    fn name(&self) -> &'static str {
+        "theban"
+    }
+
+    /// Anything without a letter is written as a space.
+    fn transliterate(&self, text: &str) -> String {
+        read_longest(text, &THEBAN, 1)
+            .into_iter()
+            .map(|(_, letters)| letters.unwrap_or(" "))
+            .collect()
+    }
+
+    fn can_write(&self, c: char) -> bool {
+        in_table(&THEBAN, c)
+    }
+
+    fn glyphs(&self) -> Vec<char> {
+        let mut glyphs: Vec<char> = THEBAN
+            .values()
+            .flat_map(|letters| letters.chars())
+            .collect();
+        glyphs.sort();
+        glyphs.dedup();
+        glyphs
+    }
+
+    fn font(&self) -> &'static str {
+        "Theban"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transliterate() {
+        assert_eq!(Theban.transliterate("Jewel-work."), "IEVVEL VVORK.");
+        assert!(!Theban.glyphs().contains(&'U'));
+    }
+}
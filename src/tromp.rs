@@ -0,0 +1,300 @@
+use svg::node::element::{Group, Line, Text};
+use svg::Document;
+
+use crate::ast::LambdaExpression;
+use crate::layout::LayoutOptions;
+
+/// The horizontal line drawn for a `LambdaExpression::Abstraction`, spanning
+/// the columns of its body.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BinderBar {
+    pub name: String,
+    pub row: usize,
+    pub left: usize,
+    /// the last column, included
+    pub right: usize,
+}
+
+/// The vertical line drawn for a variable occurrence, hanging from the bar
+/// of its binder. `binder` is the index of that bar or `None` when the
+/// variable is free, then the line starts at the top of the diagram.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VariableLeg {
+    pub name: String,
+    pub column: usize,
+    pub top: usize,
+    pub bottom: usize,
+    pub binder: Option<usize>,
+}
+
+/// The horizontal line joining the legs of the function and the argument of
+/// a `LambdaExpression::Application`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApplicationLink {
+    pub row: usize,
+    pub from: usize,
+    pub to: usize,
+}
+
+/// A classic Tromp lambda diagram on a grid, every variable occurrence gets
+/// a column and every construct a row.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrompDiagram {
+    pub bars: Vec<BinderBar>,
+    pub legs: Vec<VariableLeg>,
+    pub links: Vec<ApplicationLink>,
+    pub columns: usize,
+    pub rows: usize,
+    options: LayoutOptions,
+}
+
+/// Where a term placed on the grid ends, its leftmost leg is the one that is
+/// extended down when it is applied.
+struct Placed {
+    columns: usize,
+    /// the lowest row used by the term
+    bottom: usize,
+    /// index of the leftmost leg
+    leg: usize,
+}
+
+impl TrompDiagram {
+    pub fn new(expr: &LambdaExpression, options: &LayoutOptions) -> Self {
+        let mut diagram = TrompDiagram {
+            bars: vec![],
+            legs: vec![],
+            links: vec![],
+            columns: 0,
+            rows: 0,
+            options: *options,
+        };
+        let placed = diagram.place(expr, 0, 0, &mut vec![]);
+        // the leg of the whole term sticks out at the bottom, like in Tromp's
+        // drawings
+        diagram.legs[placed.leg].bottom = placed.bottom + 1;
+        diagram.columns = placed.columns;
+        diagram.rows = placed.bottom + 2;
+        diagram
+    }
+
+    /// Places `expr` with its top left corner at `row` and `column`, `scope`
+    /// maps the names in scope to their bar, innermost last.
+    fn place(
+        &mut self,
+        expr: &LambdaExpression,
+        row: usize,
+        column: usize,
+        scope: &mut Vec<(String, usize)>,
+    ) -> Placed {
+        match expr {
+            LambdaExpression::Variable(name) => {
+                let binder = scope
+                    .iter()
+                    .rev()
+                    .find(|(bound, _)| bound == name)
+                    .map(|(_, index)| *index);
+                self.legs.push(VariableLeg {
+                    name: name.clone(),
+                    column,
+                    top: binder.map_or(0, |binder| self.bars[binder].row),
+                    bottom: row,
+                    binder,
+                });
+                Placed {
+                    columns: 1,
+                    bottom: row,
+                    leg: self.legs.len() - 1,
+                }
+            }
+            LambdaExpression::Abstraction(name, body) => {
+                self.bars.push(BinderBar {
+                    name: name.clone(),
+                    row,
+                    left: column,
+                    right: column,
+                });
+                let bar = self.bars.len() - 1;
+                scope.push((name.clone(), bar));
+                let placed = self.place(body, row + 1, column, scope);
+                scope.pop();
+                self.bars[bar].right = column + placed.columns - 1;
+                placed
+            }
+            LambdaExpression::Application(function, argument) => {
+                let function = self.place(function, row, column, scope);
+                let argument = self.place(argument, row, column + function.columns, scope);
+                let link_row = function.bottom.max(argument.bottom) + 1;
+                self.legs[function.leg].bottom = link_row;
+                self.legs[argument.leg].bottom = link_row;
+                self.links.push(ApplicationLink {
+                    row: link_row,
+                    from: self.legs[function.leg].column,
+                    to: self.legs[argument.leg].column,
+                });
+                Placed {
+                    columns: function.columns + argument.columns,
+                    bottom: link_row,
+                    leg: function.leg,
+                }
+            }
+        }
+    }
+
+    /// Width of a column and height of a row.
+    fn unit(&self) -> f64 {
+        self.options.gap
+    }
+
+    /// Width and height of the drawing.
+    pub fn size(&self) -> (f64, f64) {
+        (
+            self.columns as f64 * self.unit(),
+            self.rows as f64 * self.unit(),
+        )
+    }
+
+    /// All elements of the diagram, with its top left corner at the origin.
+    pub fn to_group(&self) -> Group {
+        let unit = self.unit();
+        let x = |column: usize| (column as f64 + 0.5) * unit;
+        let y = |row: usize| (row as f64 + 0.5) * unit;
+        let line = |x1: f64, y1: f64, x2: f64, y2: f64| {
+            Line::new()
+                .set("x1", x1)
+                .set("y1", y1)
+                .set("x2", x2)
+                .set("y2", y2)
+        };
+
+        let mut group = Group::new()
+            .set("stroke", "black")
+            .set("stroke-width", self.options.stroke_width * 2.0)
+            .set("stroke-linecap", "square");
+        // bars stick out a little past their outermost legs
+        let overhang = unit / 4.0;
+        for bar in &self.bars {
+            group = group.add(line(
+                x(bar.left) - overhang,
+                y(bar.row),
+                x(bar.right) + overhang,
+                y(bar.row),
+            ));
+        }
+        for leg in &self.legs {
+            let top = match leg.binder {
+                Some(_) => y(leg.top),
+                None => 0.0,
+            };
+            group = group.add(line(x(leg.column), top, x(leg.column), y(leg.bottom)));
+            if leg.binder.is_none() {
+                group = group.add(
+                    Text::new(leg.name.clone())
+                        .set("x", x(leg.column))
+                        .set("y", -unit / 4.0)
+                        .set("text-anchor", "middle")
+                        .set("font-size", unit / 2.0)
+                        .set("stroke", "none"),
+                );
+            }
+        }
+        for link in &self.links {
+            group = group.add(line(x(link.from), y(link.row), x(link.to), y(link.row)));
+        }
+        group
+    }
+
+    /// A standalone document whose viewBox fits the whole diagram.
+    pub fn to_document(&self) -> Document {
+        let (width, height) = self.size();
+        let margin = self.options.margin;
+        Document::new()
+            .set(
+                "viewBox",
+                (
+                    -margin,
+                    -margin,
+                    width + 2.0 * margin,
+                    height + 2.0 * margin,
+                ),
+            )
+            .add(self.to_group())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn diagram(source: &str) -> TrompDiagram {
+        TrompDiagram::new(&parse(source).unwrap(), &LayoutOptions::default())
+    }
+
+    #[test]
+    fn test_identity() {
+        let diagram = diagram("λx.x");
+        assert_eq!(diagram.bars.len(), 1);
+        assert_eq!(
+            diagram.legs,
+            vec![VariableLeg {
+                name: "x".to_string(),
+                column: 0,
+                top: 0,
+                bottom: 2,
+                binder: Some(0),
+            }]
+        );
+        assert!(diagram.links.is_empty());
+        assert_eq!((diagram.columns, diagram.rows), (1, 3));
+    }
+
+    #[test]
+    fn test_k_binds_outer_bar() {
+        let diagram = diagram("λx.λy.x");
+        assert_eq!(diagram.bars[0].row, 0);
+        assert_eq!(diagram.bars[1].row, 1);
+        assert_eq!(diagram.legs[0].binder, Some(0));
+        assert_eq!(diagram.legs[0].top, 0);
+    }
+
+    #[test]
+    fn test_application_links_leftmost_legs() {
+        // λf.λx.f (f x)
+        let diagram = diagram("λf.λx.f (f x)");
+        assert_eq!(diagram.columns, 3);
+        assert_eq!(
+            diagram.links,
+            vec![
+                ApplicationLink {
+                    row: 3,
+                    from: 1,
+                    to: 2
+                },
+                ApplicationLink {
+                    row: 4,
+                    from: 0,
+                    to: 1
+                },
+            ]
+        );
+        // both bars span every column of their body
+        assert!(diagram
+            .bars
+            .iter()
+            .all(|bar| (bar.left, bar.right) == (0, 2)));
+        // the inner function leg reaches down to the link of the outer
+        // application
+        assert_eq!(diagram.legs[1].bottom, 4);
+    }
+
+    #[test]
+    fn test_free_variable() {
+        let diagram = diagram("f x");
+        assert_eq!(diagram.bars.len(), 0);
+        assert!(diagram
+            .legs
+            .iter()
+            .all(|leg| leg.binder.is_none() && leg.top == 0));
+        assert!(diagram.to_group().to_string().contains("\nf\n</text>"));
+    }
+}
@@ -0,0 +1,377 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::definitions::Definitions;
+use crate::ast::LambdaExpression;
+use crate::de_bruijn::binder_name;
+
+/// A simple type, built from type variables and function types only.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Type {
+    Variable(usize),
+    /// `a -> b`
+    Arrow(Box<Type>, Box<Type>),
+}
+
+fn arrow(argument: Type, result: Type) -> Type {
+    Type::Arrow(Box::new(argument), Box::new(result))
+}
+
+impl Type {
+    /// Renumbers the type variables in the order they first appear, so equal
+    /// types up to renaming print the same: `c -> d -> c` becomes
+    /// `a -> b -> a`.
+    pub fn normalize(&self) -> Type {
+        self.rename(&mut HashMap::new())
+    }
+
+    fn rename(&self, names: &mut HashMap<usize, usize>) -> Type {
+        match self {
+            Type::Variable(variable) => {
+                let next = names.len();
+                Type::Variable(*names.entry(*variable).or_insert(next))
+            }
+            Type::Arrow(argument, result) => arrow(argument.rename(names), result.rename(names)),
+        }
+    }
+
+    fn variables(&self, variables: &mut Vec<usize>) {
+        match self {
+            Type::Variable(variable) if !variables.contains(variable) => variables.push(*variable),
+            Type::Variable(_) => (),
+            Type::Arrow(argument, result) => {
+                argument.variables(variables);
+                result.variables(variables);
+            }
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    /// `->` is right associative, so only function types that are arguments
+    /// are parenthesized.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Variable(variable) => f.write_str(&binder_name(*variable)),
+            Type::Arrow(argument, result) => match argument.as_ref() {
+                Type::Arrow(_, _) => write!(f, "({}) -> {}", argument, result),
+                Type::Variable(_) => write!(f, "{} -> {}", argument, result),
+            },
+        }
+    }
+}
+
+/// A type whose `quantified` variables are replaced by fresh ones every time
+/// it is used, so a definition like `I` can be applied to values of
+/// different types.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Scheme {
+    pub quantified: Vec<usize>,
+    pub ty: Type,
+}
+
+/// `term` can't be typed because it requires a type to contain itself, as
+/// in `x x` where `x` would be a function taking itself as argument.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeError {
+    /// the application whose function and argument don't fit together
+    pub term: LambdaExpression,
+    pub variable: Type,
+    /// the type `variable` would have to be equal to, it contains `variable`
+    pub ty: Type,
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot type `{}`: `{}` would have to be `{}`, which contains itself",
+            self.term, self.variable, self.ty
+        )
+    }
+}
+
+impl std::error::Error for TypeError {}
+
+/// The state of a single inference.
+#[derive(Default)]
+struct Inference {
+    /// the type every type variable was unified with, if any
+    bindings: Vec<Option<Type>>,
+    /// free variables that aren't defined get the same type everywhere
+    free: HashMap<String, Type>,
+    /// the type of every abstraction, in the order they are laid out
+    abstractions: Vec<Type>,
+}
+
+impl Inference {
+    fn fresh(&mut self) -> Type {
+        self.bindings.push(None);
+        Type::Variable(self.bindings.len() - 1)
+    }
+
+    /// `ty` with every bound type variable replaced by what it is bound to.
+    fn resolve(&self, ty: &Type) -> Type {
+        match ty {
+            Type::Variable(variable) => match &self.bindings[*variable] {
+                Some(bound) => self.resolve(bound),
+                None => ty.clone(),
+            },
+            Type::Arrow(argument, result) => arrow(self.resolve(argument), self.resolve(result)),
+        }
+    }
+
+    fn occurs(&self, variable: usize, ty: &Type) -> bool {
+        match ty {
+            Type::Variable(other) => match &self.bindings[*other] {
+                Some(bound) => self.occurs(variable, bound),
+                None => *other == variable,
+            },
+            Type::Arrow(argument, result) => {
+                self.occurs(variable, argument) || self.occurs(variable, result)
+            }
+        }
+    }
+
+    /// Follows the bindings of `ty` until it is a function type or an unbound
+    /// variable.
+    fn prune(&self, ty: &Type) -> Type {
+        match ty {
+            Type::Variable(variable) => match &self.bindings[*variable] {
+                Some(bound) => self.prune(bound),
+                None => ty.clone(),
+            },
+            Type::Arrow(_, _) => ty.clone(),
+        }
+    }
+
+    /// Makes `a` and `b` equal, on failure returns the variable and the type
+    /// containing it that it would have to be equal to.
+    fn unify(&mut self, a: &Type, b: &Type) -> Result<(), (Type, Type)> {
+        match (self.prune(a), self.prune(b)) {
+            (Type::Variable(a), Type::Variable(b)) if a == b => Ok(()),
+            (Type::Variable(variable), other) | (other, Type::Variable(variable)) => {
+                if self.occurs(variable, &other) {
+                    return Err((Type::Variable(variable), other));
+                }
+                self.bindings[variable] = Some(other);
+                Ok(())
+            }
+            (Type::Arrow(a_argument, a_result), Type::Arrow(b_argument, b_result)) => {
+                self.unify(&a_argument, &b_argument)?;
+                self.unify(&a_result, &b_result)
+            }
+        }
+    }
+
+    fn instantiate(&mut self, scheme: &Scheme) -> Type {
+        let fresh: HashMap<usize, Type> = scheme
+            .quantified
+            .iter()
+            .map(|variable| (*variable, self.fresh()))
+            .collect();
+        fn substitute(ty: &Type, fresh: &HashMap<usize, Type>) -> Type {
+            match ty {
+                Type::Variable(variable) => fresh.get(variable).cloned().unwrap_or(ty.clone()),
+                Type::Arrow(argument, result) => {
+                    arrow(substitute(argument, fresh), substitute(result, fresh))
+                }
+            }
+        }
+        substitute(&scheme.ty, &fresh)
+    }
+
+    /// `scope` holds the types of the variables bound by the enclosing
+    /// abstractions, innermost last.
+    fn infer(
+        &mut self,
+        expr: &LambdaExpression,
+        scope: &mut Vec<(String, Type)>,
+        environment: &TypeEnvironment,
+    ) -> Result<Type, TypeError> {
+        match expr {
+            LambdaExpression::Variable(name) => {
+                if let Some((_, ty)) = scope.iter().rev().find(|(bound, _)| bound == name) {
+                    return Ok(ty.clone());
+                }
+                if let Some(scheme) = environment.schemes.get(name) {
+                    return Ok(self.instantiate(scheme));
+                }
+                if let Some(ty) = self.free.get(name) {
+                    return Ok(ty.clone());
+                }
+                let ty = self.fresh();
+                self.free.insert(name.clone(), ty.clone());
+                Ok(ty)
+            }
+            LambdaExpression::Abstraction(name, body) => {
+                let index = self.abstractions.len();
+                let argument = self.fresh();
+                self.abstractions.push(argument.clone());
+                scope.push((name.clone(), argument.clone()));
+                let result = self.infer(body, scope, environment);
+                scope.pop();
+                let ty = arrow(argument, result?);
+                self.abstractions[index] = ty.clone();
+                Ok(ty)
+            }
+            LambdaExpression::Application(function, argument) => {
+                let function_type = self.infer(function, scope, environment)?;
+                let argument_type = self.infer(argument, scope, environment)?;
+                let result = self.fresh();
+                self.unify(&function_type, &arrow(argument_type, result.clone()))
+                    .map_err(|(variable, ty)| {
+                        // name the variables of both types together, so the
+                        // variable reads the same in the type containing it
+                        let Type::Arrow(variable, ty) =
+                            arrow(self.resolve(&variable), self.resolve(&ty)).normalize()
+                        else {
+                            unreachable!("normalizing keeps the arrow");
+                        };
+                        TypeError {
+                            term: expr.clone(),
+                            variable: *variable,
+                            ty: *ty,
+                        }
+                    })?;
+                Ok(result)
+            }
+        }
+    }
+}
+
+/// The types of named terms, used for the free variables of a term that
+/// name them. Every use of a name gets a fresh copy of its type, which is
+/// let-polymorphism for the definitions of a `.lambda` file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TypeEnvironment {
+    schemes: HashMap<String, Scheme>,
+}
+
+impl TypeEnvironment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The types of `definitions`. Definitions that can't be typed, like `Y`,
+    /// are left out, so their names are typed like any other free variable.
+    ///
+    /// Only useful for terms whose names weren't expanded yet, so parsed
+    /// with `parser::parse` rather than `Definitions::parse`.
+    pub fn from_definitions(definitions: &Definitions) -> Self {
+        let mut environment = Self::new();
+        for (name, expr) in definitions.iter() {
+            if let Ok(ty) = infer(expr) {
+                environment.define(name, ty);
+            }
+        }
+        environment
+    }
+
+    /// Gives `name` the type `ty`, with all its variables quantified.
+    pub fn define(&mut self, name: impl Into<String>, ty: Type) {
+        let mut quantified = vec![];
+        ty.variables(&mut quantified);
+        self.schemes.insert(name.into(), Scheme { quantified, ty });
+    }
+
+    fn run(&self, expr: &LambdaExpression) -> Result<(Type, Inference), TypeError> {
+        let mut inference = Inference::default();
+        let ty = inference.infer(expr, &mut vec![], self)?;
+        Ok((ty, inference))
+    }
+
+    /// The principal type of `expr`, with its type variables numbered in the
+    /// order they appear.
+    pub fn infer(&self, expr: &LambdaExpression) -> Result<Type, TypeError> {
+        let (ty, inference) = self.run(expr)?;
+        Ok(inference.resolve(&ty).normalize())
+    }
+
+    /// The type of every abstraction in `expr`, in the order
+    /// `layout::Diagram` lays out their circles.
+    pub fn abstraction_types(&self, expr: &LambdaExpression) -> Result<Vec<Type>, TypeError> {
+        let (_, inference) = self.run(expr)?;
+        Ok(inference
+            .abstractions
+            .iter()
+            .map(|ty| inference.resolve(ty).normalize())
+            .collect())
+    }
+}
+
+/// The principal type of `expr`, free variables get a type variable each.
+pub fn infer(expr: &LambdaExpression) -> Result<Type, TypeError> {
+    TypeEnvironment::new().infer(expr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    macro_rules! test_infer {
+        ($name:ident, $expr:expr, $expected:expr) => {
+            ::paste::paste! {
+                #[test]
+                fn [<test_infer_$name>]() {
+                    let ty = infer(&parse($expr).unwrap()).unwrap();
+                    assert_eq!(ty.to_string(), $expected);
+                }
+            }
+        };
+    }
+
+    test_infer!(identity, "λx.x", "a -> a");
+    test_infer!(k, "λx.λy.x", "a -> b -> a");
+    test_infer!(
+        s,
+        "λx.λy.λz.(x z)(y z)",
+        "(a -> b -> c) -> (a -> b) -> a -> c"
+    );
+    test_infer!(church_numeral, "λf.λx.f(f(f(f x)))", "(a -> a) -> a -> a");
+    test_infer!(zero, "0", "a -> b -> b");
+    test_infer!(application, "(λx.λy.x) (λz.z)", "a -> b -> b");
+    test_infer!(free_variables, "f x", "a");
+    test_infer!(free_variable_used_twice, "λy.f (f y)", "a -> a");
+    test_infer!(
+        pred,
+        "λn.λf.λx.n(λg.λh.h(g f))(λu.x)(λu.u)",
+        "(((a -> b) -> (b -> c) -> c) -> (d -> e) -> (f -> f) -> g) -> a -> e -> g"
+    );
+
+    #[test]
+    fn test_occurs_check() {
+        let error = infer(&parse("λx.x x").unwrap()).unwrap_err();
+        assert_eq!(error.term, parse("x x").unwrap());
+        assert_eq!(
+            error.to_string(),
+            "cannot type `x x`: `a` would have to be `a -> b`, which contains itself"
+        );
+    }
+
+    #[test]
+    fn test_y_does_not_type() {
+        assert!(infer(&parse("λf.(λx.x x)(λx.f (x x))").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_let_polymorphism() {
+        let definitions = Definitions::prelude();
+        let environment = TypeEnvironment::from_definitions(&definitions);
+        // `I` is used both as a function on functions and on values
+        let expr = parse("λy.I I y").unwrap();
+        assert_eq!(environment.infer(&expr).unwrap().to_string(), "a -> a");
+        // lambda bound variables aren't polymorphic
+        assert!(infer(&parse("(λi.i i y) (λx.x)").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_abstraction_types() {
+        let types = TypeEnvironment::new()
+            .abstraction_types(&parse("λx.λy.x").unwrap())
+            .unwrap();
+        let types: Vec<_> = types.iter().map(Type::to_string).collect();
+        assert_eq!(types, ["a -> b -> a", "a -> b"]);
+    }
+}